@@ -24,7 +24,7 @@ fn main() {
         match &event {
             // ウィンドウイベントを受け取ったら、入力状態の更新とイベントのディスパッチを行う
             Event::WindowEvent { event, .. } => {
-                input_state.update(event);
+                input_state.update(event, window.inner_size());
                 input_dispatcher.dispatch(event);
             }
             Event::MainEventsCleared => {
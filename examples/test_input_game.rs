@@ -1,6 +1,6 @@
 // 必要なモジュールをインポートします。
 // 利用者側は run_game 関数と Game トレイトを実装するだけです。
-use mothra::{run_game, World, Renderer, InputState, Game};
+use mothra::{run_game, World, Renderer, InputState, Game, GameConfig};
 use std::time::{Duration, Instant};
 
 /// テスト用のゲームロジックを実装する構造体。
@@ -24,7 +24,7 @@ impl TestGame {
 impl Game for TestGame {
     /// 毎フレームの更新処理。
     /// ここでは更新回数をカウントし、一定回数に達したら終了します。
-    fn update(&mut self, _world: &mut World, _renderer: &mut Renderer, _input: &InputState) {
+    fn update(&mut self, _world: &mut World, _renderer: &mut Renderer, _input: &InputState, _actions: &mothra::ActionHandler, _dt: f32) {
         self.update_count += 1;
         println!("Update count: {}", self.update_count);
         // 更新回数が100回に達したら、テスト用にアプリケーションを終了する
@@ -35,24 +35,24 @@ impl Game for TestGame {
     }
 
     /// 毎フレームの描画処理。
-    /// 内部で Renderer の描画メソッドを呼び出し、World の状態に基づいた描画を行います。
-    fn render(&mut self, world: &World, renderer: &mut Renderer) {
-        // サーフェスからフレームを取得し、レンダーパスを開始する
-        let output = renderer.surface.get_current_texture().unwrap();
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = renderer
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-        
-        // Renderer の draw_world() を呼び出して、World のエンティティを描画する
-        renderer.draw_world(&mut encoder, &view, world);
-        
-        renderer.queue.submit(Some(encoder.finish()));
-        output.present();
+    /// `view`/`encoder` はすでに `App`/`run_game` 側で用意されているので、
+    /// ここでは Renderer の draw_world() を呼び出すだけでよい。
+    fn render(&mut self, world: &World, renderer: &mut Renderer, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder, _alpha: f32) {
+        renderer.draw_world(encoder, view, world);
     }
 }
 
-/// エントリーポイント。run_game() 関数に TestGame インスタンスを渡すだけで起動できます。
+/// エントリーポイント。run_game() 関数に TestGame インスタンスと GameConfig を渡すだけで起動できます。
 fn main() {
-    run_game(TestGame::new());
+    let config = GameConfig {
+        window_width: 800,
+        window_height: 600,
+        logical_width: 800,
+        logical_height: 600,
+        title: "Test Input Game".to_string(),
+        target_fps: 60,
+        stretch_mode: false,
+    };
+
+    run_game(TestGame::new(), config);
 }
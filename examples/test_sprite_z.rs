@@ -19,19 +19,19 @@ impl TestSpriteZ {
 }
 
 impl Game for TestSpriteZ {
-    fn update(&mut self, world: &mut World, renderer: &mut Renderer, _input: &InputState) {
+    fn update(&mut self, world: &mut World, renderer: &mut Renderer, _input: &InputState, _actions: &mothra::ActionHandler, _dt: f32) {
         self.update_count += 1;
         if self.update_count == 1 {
             let tex_black = self.asset_manager.load_texture(
                 &renderer.device,
                 &renderer.queue,
                 "assets/textures/black_plane_image.png",
-            );
+            ).expect("Failed to load texture 'assets/textures/black_plane_image.png'");
             let tex_white = self.asset_manager.load_texture(
                 &renderer.device,
                 &renderer.queue,
                 "assets/textures/white_plane_image.png",
-            );
+            ).expect("Failed to load texture 'assets/textures/white_plane_image.png'");
             let entities = vec![
                 (100.0, 100.0, 200.0, 200.0, 0.2, Rc::clone(&tex_black)),
                 (150.0, 150.0, 200.0, 200.0, 0.5, Rc::clone(&tex_white)),
@@ -40,13 +40,13 @@ impl Game for TestSpriteZ {
             for (x, y, w, h, z, tex) in entities {
                 let e = world.spawn();
                 world.add_transform(e, mothra::ecs::Transform { x, y, w, h, z });
-                world.add_sprite(e, mothra::ecs::Sprite { texture: tex });
+                world.add_sprite(e, mothra::ecs::Sprite::new(tex));
             }
             println!("Created entities with varying z values.");
         }
     }
 
-    fn render(&mut self, world: &World, renderer: &mut Renderer, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder) {
+    fn render(&mut self, world: &World, renderer: &mut Renderer, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder, _alpha: f32) {
         renderer.draw_sprites_batched(encoder, view, world);
     }
 }
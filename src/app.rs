@@ -0,0 +1,200 @@
+use crate::config::GameConfig;
+use crate::ecs::World;
+use crate::game::Game;
+use crate::input::{ActionHandler, InputDispatcher, InputState};
+use crate::render_graph::RenderGraph;
+use crate::renderer::Renderer;
+
+use winit::{
+    event::Event,
+    event_loop::{ControlFlow, EventLoop},
+    window::{Window, WindowBuilder},
+};
+use std::time::{Duration, Instant};
+
+/// `App` のセットアップフック。ウィンドウ/デバイス生成後、イベントループ開始前に一度だけ
+/// 呼ばれ、初期エンティティの生成や uniform の設定、アセットのプリロードなどを行う。
+pub trait Plugin {
+    fn build(&self, app: &mut App);
+}
+
+/// クロージャをそのまま `Plugin` として使えるようにするブランケット実装。
+impl<F> Plugin for F
+where
+    F: Fn(&mut App),
+{
+    fn build(&self, app: &mut App) {
+        self(app)
+    }
+}
+
+/// ウィンドウ生成・Renderer・World・プラグイン登録をまとめるビルダー。
+///
+/// `App::new(config).add_plugin(...).run(game)` の形でゲームループを起動する。
+/// `run_game` はこの builder を使った薄いラッパーとして残されている。
+pub struct App {
+    pub world: World,
+    pub renderer: Renderer,
+    pub config: GameConfig,
+    window: Window,
+    event_loop: Option<EventLoop<()>>,
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl App {
+    /// ウィンドウと Renderer、空の World を初期化して新しい App を作る。
+    ///
+    /// `Renderer::new` がアダプター/デバイス取得で await するため非同期。ネイティブでは
+    /// `run_game` が `pollster::block_on` で包み、wasm32 では `wasm_bindgen_futures::spawn_local`
+    /// から直接 await される。
+    pub async fn new(config: GameConfig) -> Self {
+        let event_loop = EventLoop::new();
+        let window = WindowBuilder::new()
+            .with_title(config.title.clone())
+            .with_inner_size(winit::dpi::LogicalSize::new(config.window_width, config.window_height))
+            .build(&event_loop)
+            .unwrap();
+
+        // wasm32 では winit のウィンドウは素の <canvas> を生成するだけなので、
+        // 自前で DOM の body に追加してやらないと画面に表示されない。
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::WindowExtWebSys;
+            web_sys::window()
+                .and_then(|win| win.document())
+                .and_then(|doc| doc.body())
+                .and_then(|body| {
+                    body.append_child(&web_sys::Element::from(window.canvas())).ok()
+                })
+                .expect("canvas を document.body に追加できませんでした");
+        }
+
+        let mut renderer = Renderer::new(&window).await;
+
+        // world は常に論理解像度のオフスクリーンターゲットへ描画するので、カメラの基準
+        // ビューポートは stretch_mode に関わらず論理解像度で固定する（ウィンドウへの引き伸ばし／
+        // レターボックスは最終ブリットパスの viewport だけが担当する）。
+        renderer.set_camera_viewport(config.logical_width as f32, config.logical_height as f32);
+
+        Self {
+            world: World::new(),
+            renderer,
+            config,
+            window,
+            event_loop: Some(event_loop),
+            plugins: Vec::new(),
+        }
+    }
+
+    /// プラグインを登録する。`run` 時にウィンドウ/デバイス生成の直後、
+    /// イベントループ開始前に、登録順に `Plugin::build` が呼ばれる。
+    pub fn add_plugin<P: Plugin + 'static>(mut self, plugin: P) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// 登録済みのプラグインを実行し、固定タイムステップのゲームループを開始する。
+    pub fn run<G: 'static + Game>(mut self, mut game: G) -> ! {
+        let plugins = std::mem::take(&mut self.plugins);
+        for plugin in &plugins {
+            plugin.build(&mut self);
+        }
+
+        let App { mut world, mut renderer, config, window, event_loop, .. } = self;
+        let event_loop = event_loop.expect("App::run を複数回呼び出すことはできません");
+
+        // レンダーグラフはゲーム側が `build_graph` をオーバーライドした場合だけノードを持つ。
+        // 空のままなら `is_empty()` が true になり、従来どおり `Game::render` を直接呼ぶ。
+        let mut graph = RenderGraph::new();
+        game.build_graph(&mut graph);
+        graph.ensure_targets(&renderer.device, renderer.surface_format, config.logical_width, config.logical_height);
+
+        let mut input = InputState::default();
+        let mut actions = ActionHandler::new();
+        game.configure_actions(&mut actions);
+        let mut dispatcher = InputDispatcher::new();
+
+        // シミュレーションは常に固定の dt で進める。フレーム描画が遅れても、
+        // 経過した実時間をアキュムレータに貯めて dt 刻みで追いつくように update を複数回走らせる。
+        let dt = 1.0 / config.target_fps as f32;
+        let fixed_dt = Duration::from_secs_f32(dt);
+        const MAX_STEPS_PER_FRAME: u32 = 5; // 長時間のストールの後にスパイラル・オブ・デスへ陥るのを防ぐ
+        let mut accumulator = Duration::ZERO;
+        let mut last_frame_time = Instant::now();
+
+        event_loop.run(move |event, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+            match event {
+                Event::WindowEvent { ref event, .. } => {
+                    input.update(event, window.inner_size());
+                    dispatcher.dispatch(event);
+                    if let winit::event::WindowEvent::Resized(new_size) = event {
+                        renderer.resize(*new_size, &config);
+                        // 中間テクスチャは論理解像度基準なのでサイズ自体は変わらないことが多いが、
+                        // ゲームがリサイズに応じてグラフの構成を変えられるよう毎回作り直す。
+                        graph.clear();
+                        game.build_graph(&mut graph);
+                        graph.ensure_targets(&renderer.device, renderer.surface_format, config.logical_width, config.logical_height);
+                    }
+                }
+                Event::MainEventsCleared => {
+                    for input_event in dispatcher.drain() {
+                        game.on_event(&input_event, &mut world);
+                    }
+
+                    let now = Instant::now();
+                    accumulator += now - last_frame_time;
+                    last_frame_time = now;
+
+                    let mut steps = 0;
+                    while accumulator >= fixed_dt && steps < MAX_STEPS_PER_FRAME {
+                        world.snapshot_transforms();
+                        actions.update(&input);
+                        game.update(&mut world, &mut renderer, &input, &actions, dt);
+                        accumulator -= fixed_dt;
+                        steps += 1;
+                    }
+                    if steps == MAX_STEPS_PER_FRAME {
+                        // 追いつけないほど遅延している場合は、アキュムレータを捨てて蓄積を防ぐ
+                        accumulator = Duration::ZERO;
+                    }
+                    // dt を使い切れず steps == 0 だった場合、この MainEventsCleared で溜まった
+                    // scroll_delta/text_buffer はまだ game.update に読まれていないので、ここで
+                    // 捨ててはいけない（次の MainEventsCleared で accumulator が fixed_dt を
+                    // 超えたときに読まれる）。
+                    if steps > 0 {
+                        input.reset_frame();
+                    }
+
+                    window.request_redraw();
+                }
+                Event::RedrawRequested(_) => {
+                    let output = match renderer.surface.get_current_texture() {
+                        Ok(frame) => frame,
+                        Err(_) => {
+                            renderer.surface.configure(&renderer.device, &renderer.config);
+                            renderer.surface.get_current_texture().expect("Failed to acquire texture")
+                        }
+                    };
+                    let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                    let mut encoder = renderer.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+                    let alpha = accumulator.as_secs_f32() / dt;
+                    if graph.is_empty() {
+                        game.render(&world, &mut renderer, &view, &mut encoder, alpha);
+                    } else {
+                        graph.execute(&mut encoder, &view, &world);
+                    }
+                    renderer.queue.submit(Some(encoder.finish()));
+                    output.present();
+                }
+                Event::WindowEvent {
+                    event: winit::event::WindowEvent::CloseRequested,
+                    ..
+                } => {
+                    *control_flow = ControlFlow::Exit;
+                }
+                _ => {}
+            }
+        });
+    }
+}
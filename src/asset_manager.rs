@@ -1,15 +1,182 @@
 // src/asset_manager.rs
 
 use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::SystemTime;
+use wgpu::util::DeviceExt;
 use wgpu::{Device, ShaderModule};
-use crate::renderer::TextureHandle;
+use crate::renderer::{MeshHandle, Rect, SpriteRegion, TextureHandle};
+
+/// アセットの読み込みに失敗したときのエラー。`.expect()` でプロセスを落とす代わりに、
+/// 呼び出し側がログに出したり、読み込み直前の状態のまま処理を続けたりできるようにする。
+#[derive(Debug)]
+pub enum AssetError {
+    /// 画像ファイルを開く、もしくはデコードすることに失敗した。
+    Image { path: String, source: image::ImageError },
+    /// シェーダーファイルの読み込み（ファイルI/O）に失敗した。
+    Io { path: String, source: std::io::Error },
+    /// WGSL のコンパイルに失敗した。`device.pop_error_scope` 経由で捕捉した検証エラー。
+    ShaderCompile { path: String, message: String },
+    /// `.obj`/`.mtl` のパース（`tobj`）に失敗した。
+    Mesh { path: String, message: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Image { path, source } => {
+                write!(f, "failed to load texture '{}': {}", path, source)
+            }
+            AssetError::Io { path, source } => {
+                write!(f, "failed to read '{}': {}", path, source)
+            }
+            AssetError::ShaderCompile { path, message } => {
+                write!(f, "failed to compile shader '{}': {}", path, message)
+            }
+            AssetError::Mesh { path, message } => {
+                write!(f, "failed to load model '{}': {}", path, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::Image { source, .. } => Some(source),
+            AssetError::Io { source, .. } => Some(source),
+            AssetError::ShaderCompile { .. } => None,
+            AssetError::Mesh { .. } => None,
+        }
+    }
+}
+
+/// キャッシュされたテクスチャがどちらの読み込み関数で作られたかを覚えておき、
+/// `reload_changed` がファイル変更を検知したときに同じ関数で読み直せるようにする。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TextureKind {
+    Plain,
+    Mipmapped,
+}
+
+/// キャッシュ済みテクスチャの mtime とロード方法。ホットリロードの判定に使う。
+struct CachedTextureMeta {
+    mtime: SystemTime,
+    kind: TextureKind,
+}
+
+/// ミップマップ生成に使う、フルスクリーン三角形でのダウンサンプルパイプライン。
+/// `load_texture_mipmapped` が初回呼び出し時に一度だけ作り、以降のテクスチャ読み込みで使い回す。
+struct MipGenPipeline {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+}
+
+/// ミップ1段を前段から1段下へボックスフィルタでダウンサンプルするだけのシェーダー。
+/// 頂点バッファを使わず `@builtin(vertex_index)` だけから全画面を覆う三角形を描く、
+/// ポストプロセスパスと同じ定番のテクニック。
+const MIPMAP_SHADER_SRC: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32((vertex_index << 1u) & 2u);
+    let y = f32(vertex_index & 2u);
+    out.uv = vec2<f32>(x, y);
+    out.position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    return out;
+}
+
+@group(0) @binding(0) var src_texture: texture_2d<f32>;
+@group(0) @binding(1) var src_sampler: sampler;
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(src_texture, src_sampler, input.uv);
+}
+"#;
+
+impl MipGenPipeline {
+    fn new(device: &Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Mipmap Downsample BindGroupLayout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mipmap Downsample PipelineLayout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mipmap Downsample Shader"),
+            source: wgpu::ShaderSource::Wgsl(MIPMAP_SHADER_SRC.into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mipmap Downsample Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Self { pipeline, bind_group_layout, sampler }
+    }
+}
 
 /// アセット管理用の構造体。
 /// テクスチャとシェーダーをキャッシュして、重複読み込みを防ぎます。
 pub struct AssetManager {
     pub textures: HashMap<String, Rc<TextureHandle>>,
     pub shaders: HashMap<String, Rc<ShaderModule>>,
+    pub meshes: HashMap<String, Rc<MeshHandle>>,
+    mip_gen_pipeline: Option<MipGenPipeline>,
+    texture_meta: HashMap<String, CachedTextureMeta>,
+    shader_mtimes: HashMap<String, SystemTime>,
 }
 
 impl AssetManager {
@@ -18,16 +185,28 @@ impl AssetManager {
         Self {
             textures: HashMap::new(),
             shaders: HashMap::new(),
+            meshes: HashMap::new(),
+            mip_gen_pipeline: None,
+            texture_meta: HashMap::new(),
+            shader_mtimes: HashMap::new(),
         }
     }
 
     /// 指定されたパスのテクスチャをキャッシュから取得、もしくは新たに読み込みます。
-    pub fn load_texture(&mut self, device: &Device, queue: &wgpu::Queue, path: &str) -> Rc<TextureHandle> {
-        if let Some(texture) = self.textures.get(path) {
-            return Rc::clone(texture);
+    ///
+    /// キャッシュは `load_texture_mipmapped` と共有しているので、同じパスが先に
+    /// `load_texture_mipmapped` 経由で読み込まれていた場合はキャッシュの種別が一致せず、
+    /// ここで読み直して `TextureKind::Plain` として上書きする。
+    pub fn load_texture(&mut self, device: &Device, queue: &wgpu::Queue, path: &str) -> Result<Rc<TextureHandle>, AssetError> {
+        if self.texture_meta.get(path).map(|meta| meta.kind) == Some(TextureKind::Plain) {
+            if let Some(texture) = self.textures.get(path) {
+                return Ok(Rc::clone(texture));
+            }
         }
         // 画像読み込み処理
-        let img = image::open(path).expect("Failed to open image").to_rgba8();
+        let img = image::open(path)
+            .map_err(|source| AssetError::Image { path: path.to_string(), source })?
+            .to_rgba8();
         let (width, height) = img.dimensions();
         let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
 
@@ -70,21 +249,339 @@ impl AssetManager {
         let texture_handle = TextureHandle { texture, view, sampler };
         let rc_handle = Rc::new(texture_handle);
         self.textures.insert(path.to_string(), Rc::clone(&rc_handle));
-        rc_handle
+        self.texture_meta.insert(path.to_string(), CachedTextureMeta { mtime: file_mtime(path), kind: TextureKind::Plain });
+        Ok(rc_handle)
+    }
+
+    /// `load_texture` と同じくキャッシュ付きで画像を読み込むが、フルのミップチェインを
+    /// GPU 上で生成する。`draw_world` で縮小表示されるスプライトのシマー・エイリアシングを
+    /// 抑えたい場合はこちらを使う（既定の `load_texture` は互換性のため `mip_level_count: 1` のまま）。
+    ///
+    /// キャッシュは `load_texture` と共有しているので、同じパスが先に `load_texture` 経由で
+    /// 読み込まれていた場合はキャッシュの種別が一致せず、ここで読み直して
+    /// `TextureKind::Mipmapped` として上書きする（さもないとミップなしのハンドルを
+    /// そのまま返してしまい、このメソッドの約束に反する）。
+    pub fn load_texture_mipmapped(&mut self, device: &Device, queue: &wgpu::Queue, path: &str) -> Result<Rc<TextureHandle>, AssetError> {
+        if self.texture_meta.get(path).map(|meta| meta.kind) == Some(TextureKind::Mipmapped) {
+            if let Some(texture) = self.textures.get(path) {
+                return Ok(Rc::clone(texture));
+            }
+        }
+        let img = image::open(path)
+            .map_err(|source| AssetError::Image { path: path.to_string(), source })?
+            .to_rgba8();
+        let (width, height) = img.dimensions();
+        let mip_count = (width.max(height) as f32).log2().floor() as u32 + 1;
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("User Texture (mipmapped)"),
+            size,
+            mip_level_count: mip_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &img,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        self.generate_mipmaps(device, queue, &texture, mip_count);
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let texture_handle = TextureHandle { texture, view, sampler };
+        let rc_handle = Rc::new(texture_handle);
+        self.textures.insert(path.to_string(), Rc::clone(&rc_handle));
+        self.texture_meta.insert(path.to_string(), CachedTextureMeta { mtime: file_mtime(path), kind: TextureKind::Mipmapped });
+        Ok(rc_handle)
+    }
+
+    /// レベル0に書き込み済みのテクスチャから、レベル1以降をボックスフィルタのダウンサンプルで
+    /// 順に埋める。パイプラインは初回だけ作って `mip_gen_pipeline` にキャッシュし、以降の
+    /// 呼び出しで使い回す。
+    fn generate_mipmaps(&mut self, device: &Device, queue: &wgpu::Queue, texture: &wgpu::Texture, mip_count: u32) {
+        if self.mip_gen_pipeline.is_none() {
+            self.mip_gen_pipeline = Some(MipGenPipeline::new(device));
+        }
+        let gen_pipeline = self.mip_gen_pipeline.as_ref().unwrap();
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Mipmap Generation Encoder"),
+        });
+        for level in 1..mip_count {
+            let src_view = texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Mip Source View"),
+                base_mip_level: level - 1,
+                mip_level_count: Some(1),
+                ..Default::default()
+            });
+            let dst_view = texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Mip Destination View"),
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                ..Default::default()
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Mipmap Downsample BindGroup"),
+                layout: &gen_pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&src_view) },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&gen_pipeline.sampler) },
+                ],
+            });
+
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Mipmap Downsample Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &dst_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), store: true },
+                })],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(&gen_pipeline.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+            drop(pass);
+        }
+        queue.submit(Some(encoder.finish()));
     }
 
     /// 指定されたパスのシェーダーをキャッシュから取得、もしくは新たに読み込みます。
-    pub fn load_shader(&mut self, device: &Device, path: &str) -> Rc<ShaderModule> {
+    /// WGSL の検証エラーは `device.push_error_scope`/`pop_error_scope` で捕捉し、
+    /// `create_shader_module` 自体がプロセスを落とすことはない。
+    pub fn load_shader(&mut self, device: &Device, path: &str) -> Result<Rc<ShaderModule>, AssetError> {
         if let Some(shader) = self.shaders.get(path) {
-            return Rc::clone(shader);
+            return Ok(Rc::clone(shader));
         }
-        let shader_src = std::fs::read_to_string(path).expect("Failed to read shader file");
+        let shader_module = self.compile_shader(device, path)?;
+        let rc_shader = Rc::new(shader_module);
+        self.shaders.insert(path.to_string(), Rc::clone(&rc_shader));
+        self.shader_mtimes.insert(path.to_string(), file_mtime(path));
+        Ok(rc_shader)
+    }
+
+    /// シェーダーファイルを読み込み、検証エラースコープ内でコンパイルする。
+    /// キャッシュへの出し入れは呼び出し側（`load_shader` と `reload_changed`）が行う。
+    ///
+    /// `pop_error_scope` は `Future` を返すが、ブラウザのメインスレッドは `block_on` で
+    /// ブロックできないため、wasm32 では検証エラーの捕捉自体を諦める（`game.rs` の
+    /// `run_game` と同じ理由・同じ `#[cfg(target_arch = "wasm32")]` の使い分け）。
+    /// スコープのポップ自体は呼び出し時点で同期的に行われるので、戻り値の `Future` を
+    /// 待たずに捨てても push/pop のネストは崩れない。
+    fn compile_shader(&self, device: &Device, path: &str) -> Result<ShaderModule, AssetError> {
+        let shader_src = std::fs::read_to_string(path)
+            .map_err(|source| AssetError::Io { path: path.to_string(), source })?;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
         let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some(path),
             source: wgpu::ShaderSource::Wgsl(shader_src.into()),
         });
-        let rc_shader = Rc::new(shader_module);
-        self.shaders.insert(path.to_string(), Rc::clone(&rc_shader));
-        rc_shader
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(AssetError::ShaderCompile { path: path.to_string(), message: error.to_string() });
+        }
+        #[cfg(target_arch = "wasm32")]
+        drop(device.pop_error_scope());
+        Ok(shader_module)
+    }
+
+    /// 指定されたパスの Wavefront `.obj` をキャッシュから取得、もしくは `tobj` で新たに
+    /// パースして読み込みます。位置・法線・UV をインターリーブした頂点バッファと
+    /// インデックスバッファを1本ずつ GPU に作り、最初に見つかったマテリアルの拡散テクスチャを
+    /// `load_texture` 経由で読み込んで（`.obj` と同じディレクトリ相対で解決する）`MeshHandle`
+    /// に添える。複数サブメッシュを持つ `.obj` でも、インデックスをオフセットして結合するので
+    /// `draw_model` は常に1回の indexed draw で描ける。
+    pub fn load_model(&mut self, device: &Device, queue: &wgpu::Queue, path: &str) -> Result<Rc<MeshHandle>, AssetError> {
+        if let Some(mesh) = self.meshes.get(path) {
+            return Ok(Rc::clone(mesh));
+        }
+
+        let load_options = tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        };
+        let (models, materials) = tobj::load_obj(path, &load_options)
+            .map_err(|source| AssetError::Mesh { path: path.to_string(), message: source.to_string() })?;
+        let materials = materials
+            .map_err(|source| AssetError::Mesh { path: path.to_string(), message: source.to_string() })?;
+
+        // position(3) + normal(3) + uv(2) でインターリーブした頂点データへ、サブメッシュごとに
+        // インデックスをオフセットしながら結合していく。
+        let mut vertex_data: Vec<f32> = Vec::new();
+        let mut index_data: Vec<u32> = Vec::new();
+        for model in &models {
+            let mesh = &model.mesh;
+            let vertex_base = (vertex_data.len() / 8) as u32;
+            let vertex_count = mesh.positions.len() / 3;
+            let has_normals = mesh.normals.len() == mesh.positions.len();
+            let has_texcoords = mesh.texcoords.len() == vertex_count * 2;
+            for i in 0..vertex_count {
+                vertex_data.extend_from_slice(&mesh.positions[i * 3..i * 3 + 3]);
+                if has_normals {
+                    vertex_data.extend_from_slice(&mesh.normals[i * 3..i * 3 + 3]);
+                } else {
+                    vertex_data.extend_from_slice(&[0.0, 0.0, 0.0]);
+                }
+                if has_texcoords {
+                    vertex_data.extend_from_slice(&mesh.texcoords[i * 2..i * 2 + 2]);
+                } else {
+                    vertex_data.extend_from_slice(&[0.0, 0.0]);
+                }
+            }
+            index_data.extend(mesh.indices.iter().map(|&idx| idx + vertex_base));
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(path),
+            contents: bytemuck::cast_slice(&vertex_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(path),
+            contents: bytemuck::cast_slice(&index_data),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // マルチマテリアルの .obj はサブメッシュごとに描き分ける必要があるが、draw_model は
+        // 1回の indexed draw しか発行しないので、モデル全体で1枚の拡散テクスチャに簡略化する。
+        let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new("."));
+        let diffuse_texture = materials
+            .iter()
+            .find_map(|material| material.diffuse_texture.as_ref())
+            .map(|filename| base_dir.join(filename))
+            .and_then(|texture_path| texture_path.to_str().map(str::to_string))
+            .and_then(|texture_path| self.load_texture(device, queue, &texture_path).ok());
+
+        let mesh_handle = Rc::new(MeshHandle {
+            vertex_buffer,
+            index_buffer,
+            index_count: index_data.len() as u32,
+            diffuse_texture,
+        });
+        self.meshes.insert(path.to_string(), Rc::clone(&mesh_handle));
+        Ok(mesh_handle)
     }
+
+    /// スプライトシートを（`load_texture` 経由で）1回だけ読み込み、`frames` で指定した
+    /// ピクセル単位の矩形ごとに、シート全体を共有する `SpriteRegion` を作る。同じシートから
+    /// 作った `SpriteRegion` はすべて同じ `Rc<TextureHandle>` を指すため、`draw_world` は
+    /// フレームが違っても1枚のテクスチャ・1回の bind group としてバッチ化できる。
+    pub fn load_atlas(
+        &mut self,
+        device: &Device,
+        queue: &wgpu::Queue,
+        path: &str,
+        frames: &HashMap<String, Rect>,
+    ) -> Result<HashMap<String, SpriteRegion>, AssetError> {
+        let texture = self.load_texture(device, queue, path)?;
+        let atlas_size = texture.texture.size();
+        let (atlas_w, atlas_h) = (atlas_size.width as f32, atlas_size.height as f32);
+
+        let regions = frames
+            .iter()
+            .map(|(name, rect)| {
+                let region = SpriteRegion {
+                    texture: Rc::clone(&texture),
+                    uv_min: [rect.x as f32 / atlas_w, rect.y as f32 / atlas_h],
+                    uv_max: [(rect.x + rect.w) as f32 / atlas_w, (rect.y + rect.h) as f32 / atlas_h],
+                };
+                (name.clone(), region)
+            })
+            .collect();
+        Ok(regions)
+    }
+
+    /// キャッシュ済みの全テクスチャ・シェーダーの mtime を調べ、ファイルが更新されているものだけ
+    /// 読み直してキャッシュの `Rc` を差し替える。コンパイル・デコードに失敗したファイルはログに
+    /// 警告を出すだけでキャッシュを古いまま保ち、1つの壊れたアセットでプロセス全体を落とさない。
+    /// 戻り値は実際に読み直しに成功したキャッシュキーの一覧。
+    pub fn reload_changed(&mut self, device: &Device, queue: &wgpu::Queue) -> Vec<String> {
+        let mut reloaded = Vec::new();
+
+        let texture_paths: Vec<String> = self.texture_meta.keys().cloned().collect();
+        for path in texture_paths {
+            let meta = &self.texture_meta[&path];
+            if file_mtime(&path) <= meta.mtime {
+                continue;
+            }
+            let mipmapped = meta.kind == TextureKind::Mipmapped;
+            match self.load_texture_uncached(device, queue, &path, mipmapped) {
+                Ok(_) => reloaded.push(path),
+                Err(err) => log::warn!(target: "rendering", "reload_changed: {}", err),
+            }
+        }
+
+        let shader_paths: Vec<String> = self.shader_mtimes.keys().cloned().collect();
+        for path in shader_paths {
+            let mtime = file_mtime(&path);
+            if mtime <= self.shader_mtimes[&path] {
+                continue;
+            }
+            match self.compile_shader(device, &path) {
+                Ok(shader_module) => {
+                    self.shaders.insert(path.clone(), Rc::new(shader_module));
+                    self.shader_mtimes.insert(path.clone(), mtime);
+                    reloaded.push(path);
+                }
+                Err(err) => {
+                    log::warn!(target: "rendering", "reload_changed: {}", err);
+                }
+            }
+        }
+
+        reloaded
+    }
+
+    /// `load_texture`/`load_texture_mipmapped` と同じ読み込み処理だが、キャッシュを見ずに
+    /// 必ずファイルから読み直す。`reload_changed` が変更を検知したときに使う。
+    fn load_texture_uncached(&mut self, device: &Device, queue: &wgpu::Queue, path: &str, mipmapped: bool) -> Result<Rc<TextureHandle>, AssetError> {
+        let previous = self.textures.remove(path);
+        let result = if mipmapped {
+            self.load_texture_mipmapped(device, queue, path)
+        } else {
+            self.load_texture(device, queue, path)
+        };
+        // 読み直しに失敗したら、古いハンドルをキャッシュへ戻して使い続けられるようにする
+        if result.is_err() {
+            if let Some(previous) = previous {
+                self.textures.insert(path.to_string(), previous);
+            }
+        }
+        result
+    }
+}
+
+/// ファイルの最終更新時刻を調べる。メタデータが取れない（削除された、権限がないなど）場合は
+/// `UNIX_EPOCH` を返し、「常に古い」扱いにして次の `reload_changed` で読み直しを試みさせる。
+fn file_mtime(path: &str) -> SystemTime {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
 }
@@ -1,79 +1,256 @@
-use std::collections::HashMap;
-use crate::renderer::TextureHandle;
-
-use std::rc::Rc;
-
-/// エンティティID（ただの整数）
-pub type Entity = u32;
-
-/// Entityの2D座標とサイズ情報
-#[derive(Clone, Copy, Debug)]
-pub struct Transform {
-    pub x: f32,
-    pub y: f32,
-    pub w: f32,
-    pub h: f32,
-    pub z: f32, // 追加: 描画順（低いほど奥）
-}
-
-/// テクスチャを参照する Sprite コンポーネント（共有参照）
-pub struct Sprite {
-    pub texture: Rc<TextureHandle>,
-}
-
-/// World は Entity / Component を保持・操作する構造体
-pub struct World {
-    next_entity: Entity,
-    transforms: HashMap<Entity, Transform>,
-    sprites: HashMap<Entity, Sprite>,
-}
-
-impl World {
-    /// 新しいワールドを作成
-    pub fn new() -> Self {
-        Self {
-            next_entity: 0,
-            transforms: HashMap::new(),
-            sprites: HashMap::new(),
-        }
-    }
-
-    /// 新しい Entity を生成して返す
-    pub fn spawn(&mut self) -> Entity {
-        let id = self.next_entity;
-        self.next_entity += 1;
-        id
-    }
-
-    /// Entity に Transform を追加
-    pub fn add_transform(&mut self, entity: Entity, transform: Transform) {
-        self.transforms.insert(entity, transform);
-    }
-
-    /// Entity に Sprite を追加
-    pub fn add_sprite(&mut self, entity: Entity, sprite: Sprite) {
-        self.sprites.insert(entity, sprite);
-    }
-
-    /// 描画対象のEntityを取得（TransformとSpriteを両方持っているもの）
-    pub fn query_drawables(&self) -> Vec<(Transform, &TextureHandle)> {
-        self.transforms
-            .iter()
-            .filter_map(|(&e, t)| {
-                self.sprites.get(&e).map(|s| (*t, s.texture.as_ref()))
-            })
-            .collect()
-    }
-
-    /// 描画対象のエンティティを、Transform と Sprite（テクスチャ）のペアとして返す。
-    /// さらに、Transform の z 値でソートして、描画順（奥から手前）を確定する。
-    pub fn query_drawables_with_z(&self) -> Vec<(Transform, Rc<TextureHandle>)> {
-        let mut drawables: Vec<(Transform, Rc<TextureHandle>)> = self.transforms.iter()
-            .filter_map(|(&entity, &transform)| {
-                self.sprites.get(&entity).map(|sprite| (transform, Rc::clone(&sprite.texture)))
-            })
-            .collect();
-        drawables.sort_by(|(t1, _), (t2, _)| t1.z.partial_cmp(&t2.z).unwrap());
-        drawables
-    }
-}
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use crate::renderer::{BlendMode, SpriteRegion, TextureHandle};
+
+use std::rc::Rc;
+
+/// エンティティID（ただの整数）
+pub type Entity = u32;
+
+/// Entityの2D座標とサイズ情報
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub z: f32, // 追加: 描画順（低いほど奥）
+}
+
+/// テクスチャ（あるいはアトラス内の1フレーム）を参照する Sprite コンポーネント。
+pub struct Sprite {
+    /// 描画するテクスチャと UV 範囲。アトラスを使わない場合は `SpriteRegion::full` で
+    /// テクスチャ全体（UV [0,1]）を指す。
+    pub region: SpriteRegion,
+    /// 描画時にテクスチャ色へ乗算する RGBA（tint）。既定は不透明の白＝無変化。
+    pub color_multiply: [f32; 4],
+    /// 描画時にテクスチャ色へ加算する RGBA。フラッシュ演出などに使う。既定は無変化。
+    pub color_add: [f32; 4],
+    /// バッチ描画時の合成方法。既定は通常のアルファブレンディング。
+    pub blend_mode: BlendMode,
+}
+
+impl Sprite {
+    /// テクスチャ全体（UV [0,1]）を表示する、色変換なし・通常合成の Sprite を作る。
+    pub fn new(texture: Rc<TextureHandle>) -> Self {
+        Self::from_region(SpriteRegion::full(texture))
+    }
+
+    /// アトラス内の1フレームを表示する、色変換なし・通常合成の Sprite を作る。
+    pub fn from_region(region: SpriteRegion) -> Self {
+        Self {
+            region,
+            color_multiply: [1.0, 1.0, 1.0, 1.0],
+            color_add: [0.0, 0.0, 0.0, 0.0],
+            blend_mode: BlendMode::Normal,
+        }
+    }
+}
+
+/// 描画するテキストを保持するコンポーネント。`Transform` と組み合わせて使い、
+/// `Transform.x, Transform.y` が文字列のベースライン（左端・ベースライン上）になる。
+pub struct Text {
+    pub content: String,
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl Text {
+    /// 不透明な白色の Text を作る。
+    pub fn new(content: impl Into<String>, size: f32) -> Self {
+        Self {
+            content: content.into(),
+            size,
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+}
+
+/// 型ごとのコンポーネントストア。`World` はこれを `TypeId` で引けるよう型消去して保持する。
+trait ComponentStore: Any {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> ComponentStore for HashMap<Entity, T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(&entity);
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// World は Entity / Component を保持・操作する構造体。
+///
+/// コンポーネントの種類ごとに `HashMap` を手で用意するのではなく、
+/// コンポーネント型の `TypeId` をキーにした型消去ストアのレジストリとして保持する。
+/// 新しいコンポーネント型は `add_component::<T>` を呼ぶだけで登録でき、
+/// `World` 自体に手を加える必要はない。
+pub struct World {
+    next_entity: Entity,
+    stores: HashMap<TypeId, Box<dyn ComponentStore>>,
+    // 固定タイムステップの補間用に、直前のシミュレーションステップ時点の Transform を保持する
+    prev_transforms: HashMap<Entity, Transform>,
+}
+
+impl World {
+    /// 新しいワールドを作成
+    pub fn new() -> Self {
+        Self {
+            next_entity: 0,
+            stores: HashMap::new(),
+            prev_transforms: HashMap::new(),
+        }
+    }
+
+    /// 新しい Entity を生成して返す
+    pub fn spawn(&mut self) -> Entity {
+        let id = self.next_entity;
+        self.next_entity += 1;
+        id
+    }
+
+    /// Entity とそれに紐づく全コンポーネントを取り除く
+    pub fn despawn(&mut self, entity: Entity) {
+        for store in self.stores.values_mut() {
+            store.remove_entity(entity);
+        }
+        self.prev_transforms.remove(&entity);
+    }
+
+    fn store<T: 'static>(&self) -> Option<&HashMap<Entity, T>> {
+        self.stores
+            .get(&TypeId::of::<T>())
+            .map(|store| store.as_any().downcast_ref::<HashMap<Entity, T>>().unwrap())
+    }
+
+    fn store_mut<T: 'static>(&mut self) -> &mut HashMap<Entity, T> {
+        self.stores
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(HashMap::<Entity, T>::new()))
+            .as_any_mut()
+            .downcast_mut::<HashMap<Entity, T>>()
+            .unwrap()
+    }
+
+    /// Entity に任意の型のコンポーネントを追加する
+    pub fn add_component<T: 'static>(&mut self, entity: Entity, component: T) {
+        self.store_mut::<T>().insert(entity, component);
+    }
+
+    /// Entity からコンポーネントを取り除く
+    pub fn remove_component<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.store_mut::<T>().remove(&entity)
+    }
+
+    /// Entity が持つコンポーネントへの参照を取得する
+    pub fn get_component<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        self.store::<T>()?.get(&entity)
+    }
+
+    /// Entity が持つコンポーネントへの可変参照を取得する
+    pub fn get_component_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.store_mut::<T>().get_mut(&entity)
+    }
+
+    /// 指定した型のコンポーネントを持つ全エンティティを走査する
+    pub fn query<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.store::<T>()
+            .into_iter()
+            .flat_map(|store| store.iter().map(|(&entity, component)| (entity, component)))
+    }
+
+    /// `A` と `B` の両方のコンポーネントを持つ全エンティティを走査する。`A` ストアを基準に
+    /// 走査しつつ各エンティティの `B` を引くので、`A` の方が少数派のコンポーネントになる
+    /// 呼び出し順にすると走査回数が減る（例: `query2::<Transform, Sprite>()`）。
+    pub fn query2<A: 'static, B: 'static>(&self) -> impl Iterator<Item = (Entity, &A, &B)> {
+        self.query::<A>()
+            .filter_map(move |(entity, a)| self.get_component::<B>(entity).map(|b| (entity, a, b)))
+    }
+
+    /// Entity に Transform を追加する（`add_component::<Transform>` の糖衣構文）
+    pub fn add_transform(&mut self, entity: Entity, transform: Transform) {
+        self.add_component(entity, transform);
+    }
+
+    /// Entity に Sprite を追加する（`add_component::<Sprite>` の糖衣構文）
+    pub fn add_sprite(&mut self, entity: Entity, sprite: Sprite) {
+        self.add_component(entity, sprite);
+    }
+
+    /// Entity に Text を追加する（`add_component::<Text>` の糖衣構文）
+    pub fn add_text(&mut self, entity: Entity, text: Text) {
+        self.add_component(entity, text);
+    }
+
+    /// 描画対象のEntityを取得（TransformとSpriteを両方持っているもの）
+    pub fn query_drawables(&self) -> Vec<(Transform, &TextureHandle)> {
+        self.query2::<Transform, Sprite>()
+            .map(|(_, transform, sprite)| (*transform, sprite.region.texture.as_ref()))
+            .collect()
+    }
+
+    /// 描画対象のエンティティを、Transform・テクスチャ・色変換（乗算・加算）・合成方法・
+    /// UV範囲（uv_min, uv_max）の組として返す。さらに、Transform の z 値で降順（手前から奥）に
+    /// ソートする。深度テストが LessEqual なので z が異なる不透明スプライト同士の最終的な見た目は
+    /// ソート順に依存しないが、手前の
+    /// 不透明なジオメトリから先に描けば深度バッファが早く埋まり、後続のオクルードされた
+    /// フラグメントが早期 z カリングで破棄されやすくなる。
+    pub fn query_drawables_with_z(&self) -> Vec<(Transform, Rc<TextureHandle>, [f32; 4], [f32; 4], BlendMode, [f32; 2], [f32; 2])> {
+        let mut drawables: Vec<(Transform, Rc<TextureHandle>, [f32; 4], [f32; 4], BlendMode, [f32; 2], [f32; 2])> = self
+            .query2::<Transform, Sprite>()
+            .map(|(_, transform, sprite)| {
+                (
+                    *transform,
+                    Rc::clone(&sprite.region.texture),
+                    sprite.color_multiply,
+                    sprite.color_add,
+                    sprite.blend_mode,
+                    sprite.region.uv_min,
+                    sprite.region.uv_max,
+                )
+            })
+            .collect();
+        drawables.sort_by(|(t1, ..), (t2, ..)| t2.z.partial_cmp(&t1.z).unwrap());
+        drawables
+    }
+
+    /// 描画対象のテキストエンティティを、ベースライン位置（Transform）と Text の組として返す。
+    pub fn query_texts(&self) -> Vec<(Transform, &Text)> {
+        self.query2::<Transform, Text>()
+            .map(|(_, transform, text)| (*transform, text))
+            .collect()
+    }
+
+    /// 固定タイムステップの各シミュレーションステップの直前に呼び出し、現在の Transform を
+    /// 「直前の状態」として記録する。`interpolated_transform` で描画時の補間に使う。
+    pub fn snapshot_transforms(&mut self) {
+        self.prev_transforms = self.store::<Transform>().cloned().unwrap_or_default();
+    }
+
+    /// 直前のシミュレーションステップ時点の Transform を返す（未記録の場合は現在の値）
+    pub fn prev_transform(&self, entity: Entity) -> Option<Transform> {
+        self.prev_transforms
+            .get(&entity)
+            .copied()
+            .or_else(|| self.get_component::<Transform>(entity).copied())
+    }
+
+    /// `alpha` (0.0 = 直前のステップ, 1.0 = 現在のステップ) で Transform を線形補間する。
+    pub fn interpolated_transform(&self, entity: Entity, alpha: f32) -> Option<Transform> {
+        let current = *self.get_component::<Transform>(entity)?;
+        let prev = self.prev_transform(entity).unwrap_or(current);
+        Some(Transform {
+            x: prev.x + (current.x - prev.x) * alpha,
+            y: prev.y + (current.y - prev.y) * alpha,
+            w: prev.w + (current.w - prev.w) * alpha,
+            h: prev.h + (current.h - prev.h) * alpha,
+            z: current.z,
+        })
+    }
+}
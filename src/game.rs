@@ -1,96 +1,53 @@
-use crate::ecs::World;
-use crate::input::InputState;
-use crate::renderer::Renderer;
-use crate::config::GameConfig;
-
-use winit::{
-    event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
-};
-use pollster::block_on;
-use std::time::{Duration, Instant};
-
-
-/// ゲームのメインロジックを定義するトレイト。
-pub trait Game {
-    /// 毎フレームの更新処理。
-    fn update(&mut self, world: &mut World, renderer: &mut Renderer, input: &InputState);
-    /// 毎フレームの描画処理。`view` と `encoder` を使って描画コマンドを記録する。
-    fn render(&mut self, world: &World, renderer: &mut Renderer, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder);
-}
-
-/// run_game 関数
-///
-/// この関数は、Gameトレイトを実装したゲームロジックと設定情報(GameConfig)を受け取り、
-/// 内部でウィンドウ生成、Renderer、World、InputState の初期化、FPS制御付きイベントループを管理します。
-pub fn run_game<G: 'static + Game>(mut game: G, config: GameConfig) -> ! {
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new()
-        .with_title(config.title.clone())
-        .with_inner_size(winit::dpi::LogicalSize::new(config.window_width, config.window_height))
-        .build(&event_loop)
-        .unwrap();
-
-    let mut renderer = pollster::block_on(Renderer::new(&window));
-    let mut world = crate::ecs::World::new();
-    let mut input = crate::input::InputState::default();
-
-    // 初期化時、stretch_mode に合わせて uniform を設定
-    {
-        let scale = if config.stretch_mode {
-            [2.0 / config.window_width as f32, 2.0 / config.window_height as f32]
-        } else {
-            [2.0 / config.logical_width as f32, 2.0 / config.logical_height as f32]
-        };
-        renderer.update_uniform(&scale);
-    }
-
-    let target_frame_duration = std::time::Duration::from_millis(1000 / config.target_fps as u64);
-    let mut last_frame_time = std::time::Instant::now();
-
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
-        match event {
-            Event::WindowEvent { ref event, .. } => {
-                input.update(event);
-                if let winit::event::WindowEvent::Resized(new_size) = event {
-                    renderer.resize(*new_size, &config);
-                }
-            }
-            Event::MainEventsCleared => {
-                let now = std::time::Instant::now();
-                let elapsed = now - last_frame_time;
-                if elapsed < target_frame_duration {
-                    *control_flow = ControlFlow::WaitUntil(now + target_frame_duration - elapsed);
-                } else {
-                    last_frame_time = now;
-                    game.update(&mut world, &mut renderer, &input);
-                    window.request_redraw();
-                }
-            }
-            Event::RedrawRequested(_) => {
-                let output = match renderer.surface.get_current_texture() {
-                    Ok(frame) => frame,
-                    Err(_) => {
-                        renderer.surface.configure(&renderer.device, &renderer.config);
-                        renderer.surface.get_current_texture().expect("Failed to acquire texture")
-                    }
-                };
-                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-                let mut encoder = renderer.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-                game.render(&world, &mut renderer, &view, &mut encoder);
-                renderer.queue.submit(Some(encoder.finish()));
-                output.present();
-            }
-            Event::WindowEvent {
-                event: winit::event::WindowEvent::CloseRequested,
-                ..
-            } => {
-                *control_flow = ControlFlow::Exit;
-            }
-            _ => {}
-        }
-    });
-}
-
+use crate::app::App;
+use crate::config::GameConfig;
+use crate::ecs::World;
+use crate::input::{ActionHandler, InputEvent, InputState};
+use crate::render_graph::RenderGraph;
+use crate::renderer::Renderer;
+
+/// ゲームのメインロジックを定義するトレイト。
+pub trait Game {
+    /// アクションマッピングの初期設定。起動時に一度だけ呼ばれる。
+    /// ここで `actions.register_action` と `ActionHandler::push_layout` を使って
+    /// 物理入力と論理アクションのバインディングを設定する。
+    fn configure_actions(&self, _actions: &mut ActionHandler) {}
+    /// 宣言的なレンダーグラフを組み立てる任意のフック。起動時に一度、リサイズのたびに
+    /// 呼び直される。既定では何もせず、その場合 `graph` にはノードが1つも追加されないので
+    /// `App` はこれまで通り `render` を直接呼ぶ。シェーダーパスをグラフとして宣言したいゲームだけ
+    /// オーバーライドすればよい。
+    fn build_graph(&mut self, _graph: &mut RenderGraph) {}
+    /// 離散的な入力イベント（キー押下、ボタン解放、ホイール回転など）を受け取るコールバック。
+    /// `InputState` のスナップショット差分を取らなくても、1回だけ確実に発火するイベントを
+    /// 扱える。エッジトリガなイベントはここで、連続的な状態は `update` で処理するのが基本。
+    fn on_event(&mut self, _event: &InputEvent, _world: &mut World) {}
+    /// 固定タイムステップのシミュレーション更新。`dt` は常に `1.0 / target_fps` で一定であり、
+    /// フレームレートが乱れても物理挙動が変化しないようにするための値。
+    /// `actions` は直前の `InputState` から再計算済みのアクション値。
+    fn update(&mut self, world: &mut World, renderer: &mut Renderer, input: &InputState, actions: &ActionHandler, dt: f32);
+    /// 毎フレームの描画処理。`view` と `encoder` を使って描画コマンドを記録する。
+    /// `alpha` (0.0〜1.0) は直前と現在のシミュレーションステップの間の補間係数で、
+    /// `World::interpolated_transform` と組み合わせて滑らかな動きを描画するために使う。
+    fn render(&mut self, world: &World, renderer: &mut Renderer, view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder, alpha: f32);
+}
+
+/// run_game 関数
+///
+/// `App::new(config).run(game)` の薄いラッパー。プラグインによる拡張が不要な
+/// 単純なケース向けに、これまで通りの呼び出し方を維持する。
+///
+/// `App::new` は非同期になったため、ネイティブ向けにはここで `pollster::block_on` して
+/// 従来どおり同期関数として呼び出せるようにしている。wasm32 ではブラウザのイベントループを
+/// ブロックできないので、`spawn_local` でタスクとして起動する版に差し替わる。
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run_game<G: 'static + Game>(game: G, config: GameConfig) -> ! {
+    pollster::block_on(App::new(config)).run(game)
+}
+
+/// wasm32 向けの `run_game`。ブラウザのメインスレッドは `block_on` できないため、
+/// `App::new` の完了を待ってから `run` するタスクを `spawn_local` に投げて即座に返る。
+#[cfg(target_arch = "wasm32")]
+pub fn run_game<G: 'static + Game>(game: G, config: GameConfig) {
+    wasm_bindgen_futures::spawn_local(async move {
+        App::new(config).await.run(game);
+    });
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 // 入力イベントを表す列挙型
 #[derive(Debug)]
 pub enum InputEvent {
@@ -6,9 +8,26 @@ pub enum InputEvent {
     MouseMoved { x: f64, y: f64 },
     MouseButtonPressed(winit::event::MouseButton),
     MouseButtonReleased(winit::event::MouseButton),
+    /// マウスホイールのスクロール量。`LineDelta`/`PixelDelta` のいずれも同じ単位に正規化される。
+    MouseWheel { delta_x: f32, delta_y: f32 },
+    /// IME等を経由して確定した1文字（制御文字は除外済み）
+    TextEntered(char),
     // 将来的にタッチ入力なども追加可能
 }
 
+/// `LineDelta`（ホイール1段分の整数ステップ）と `PixelDelta`（トラックパッドの生ピクセル量）を
+/// 同じスクロール単位に正規化する。1ラインを `LINE_DELTA_PIXELS` ピクセル相当として換算する。
+const LINE_DELTA_PIXELS: f32 = 16.0;
+
+fn normalize_scroll_delta(delta: &winit::event::MouseScrollDelta) -> (f32, f32) {
+    match delta {
+        winit::event::MouseScrollDelta::LineDelta(x, y) => (*x, *y),
+        winit::event::MouseScrollDelta::PixelDelta(pos) => {
+            (pos.x as f32 / LINE_DELTA_PIXELS, pos.y as f32 / LINE_DELTA_PIXELS)
+        }
+    }
+}
+
 /// 入力状態を保持する構造体
 #[derive(Default, Debug)]
 pub struct InputState {
@@ -18,12 +37,20 @@ pub struct InputState {
     pub mouse_buttons: Vec<winit::event::MouseButton>,
     /// カーソルの現在位置（ウィンドウ座標）
     pub cursor_position: Option<(f64, f64)>,
+    /// カーソル位置をウィンドウサイズで正規化したもの。原点は左上、範囲は [0.0, 1.0)。
+    /// 解像度に依存しないゲームロジックを書くために使う。
+    pub cursor_normalized: Option<(f32, f32)>,
+    /// このフレームのホイールスクロール量。フレームごとにリセットされる。
+    pub scroll_delta: (f32, f32),
+    /// このフレームに確定した文字入力。フレームごとにリセットされる。
+    pub text_buffer: String,
 }
 
 impl InputState {
-    /// 与えられたウィンドウイベントに基づき入力状態を更新する
-    pub fn update(&mut self, event: &winit::event::WindowEvent) {
-        use winit::event::{ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+    /// 与えられたウィンドウイベントに基づき入力状態を更新する。
+    /// `window_size` は `cursor_normalized` の計算に使う物理ウィンドウサイズ。
+    pub fn update(&mut self, event: &winit::event::WindowEvent, window_size: winit::dpi::PhysicalSize<u32>) {
+        use winit::event::{ElementState, WindowEvent};
 
         match event {
             WindowEvent::KeyboardInput { input, .. } => {
@@ -54,10 +81,33 @@ impl InputState {
             }
             WindowEvent::CursorMoved { position, .. } => {
                 self.cursor_position = Some((position.x, position.y));
+                if window_size.width > 0 && window_size.height > 0 {
+                    self.cursor_normalized = Some((
+                        (position.x as f32 / window_size.width as f32).clamp(0.0, 1.0 - f32::EPSILON),
+                        (position.y as f32 / window_size.height as f32).clamp(0.0, 1.0 - f32::EPSILON),
+                    ));
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (dx, dy) = normalize_scroll_delta(delta);
+                self.scroll_delta.0 += dx;
+                self.scroll_delta.1 += dy;
+            }
+            WindowEvent::ReceivedCharacter(ch) => {
+                if !ch.is_control() {
+                    self.text_buffer.push(*ch);
+                }
             }
             _ => {}
         }
     }
+
+    /// フレームごとにリセットすべき状態（スクロール量、確定文字）をクリアする。
+    /// `run_game` が各フレームの更新後に呼び出す。
+    pub fn reset_frame(&mut self) {
+        self.scroll_delta = (0.0, 0.0);
+        self.text_buffer.clear();
+    }
 }
 
 /// 入力イベントをディスパッチする機能を持つ構造体
@@ -104,6 +154,15 @@ impl InputDispatcher {
                     }
                 }
             }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (delta_x, delta_y) = normalize_scroll_delta(delta);
+                self.events.push(InputEvent::MouseWheel { delta_x, delta_y });
+            }
+            WindowEvent::ReceivedCharacter(ch) => {
+                if !ch.is_control() {
+                    self.events.push(InputEvent::TextEntered(*ch));
+                }
+            }
             _ => {}
         }
     }
@@ -113,3 +172,152 @@ impl InputDispatcher {
         self.events.drain(..).collect()
     }
 }
+
+/// 論理アクションの種類。
+/// `Button` は 0.0/1.0 の二値、`Axis` は [-1.0, 1.0] の連続値を取る。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Button,
+    Axis,
+}
+
+/// アクションに結びつく物理入力。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionSource {
+    Key(winit::event::VirtualKeyCode),
+    MouseButton(winit::event::MouseButton),
+}
+
+/// 物理入力（キー/マウスボタン）と論理アクション名を結びつけるバインディング。
+/// `scale` は押下中に加算される値で、例えば `W -> "move_vertical" (+1.0)`、
+/// `S -> "move_vertical" (-1.0)` のように同じ軸に重ねることでアナログ軸を表現する。
+#[derive(Debug, Clone)]
+pub struct ActionBinding {
+    pub source: ActionSource,
+    pub action: String,
+    pub scale: f32,
+}
+
+/// バインディングの名前付き集合。`ActionHandler` にスタックとして push/pop でき、
+/// メニューとゲームプレイなど状況ごとに異なるマッピングを切り替えられる。
+#[derive(Debug, Clone, Default)]
+pub struct ActionLayout {
+    pub name: String,
+    pub bindings: Vec<ActionBinding>,
+}
+
+impl ActionLayout {
+    /// 新しい空のレイアウトを生成する
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), bindings: Vec::new() }
+    }
+
+    /// キーをアクションにバインドする（ビルダースタイルで連鎖可能）
+    pub fn bind_key(mut self, key: winit::event::VirtualKeyCode, action: &str, scale: f32) -> Self {
+        self.bindings.push(ActionBinding {
+            source: ActionSource::Key(key),
+            action: action.to_string(),
+            scale,
+        });
+        self
+    }
+
+    /// マウスボタンをアクションにバインドする（ビルダースタイルで連鎖可能）
+    pub fn bind_mouse_button(mut self, button: winit::event::MouseButton, action: &str, scale: f32) -> Self {
+        self.bindings.push(ActionBinding {
+            source: ActionSource::MouseButton(button),
+            action: action.to_string(),
+            scale,
+        });
+        self
+    }
+}
+
+/// 物理入力を論理アクションへ変換するサブシステム。
+///
+/// `InputState` を直接ポーリングする代わりに、ユーザーは名前付きアクションを登録し、
+/// レイアウトをスタックで push/pop することで入力マッピングを切り替えられる。
+/// 毎フレーム `InputState::update` の後に `update` を呼び、`action_value` / `action_just_pressed`
+/// でゲームロジックから参照する。
+#[derive(Debug, Default)]
+pub struct ActionHandler {
+    actions: HashMap<String, ActionKind>,
+    values: HashMap<String, f32>,
+    previous_values: HashMap<String, f32>,
+    layouts: Vec<ActionLayout>,
+}
+
+impl ActionHandler {
+    /// 新しい ActionHandler を生成する
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 論理アクションを登録する。登録済みの場合は何もしない。
+    pub fn register_action(&mut self, name: &str, kind: ActionKind) {
+        self.actions.entry(name.to_string()).or_insert(kind);
+        self.values.entry(name.to_string()).or_insert(0.0);
+        self.previous_values.entry(name.to_string()).or_insert(0.0);
+    }
+
+    /// レイアウトをスタックの先頭に積む（以後、このレイアウトのバインディングも評価される）
+    pub fn push_layout(&mut self, layout: ActionLayout) {
+        self.layouts.push(layout);
+    }
+
+    /// スタックの先頭のレイアウトを取り除く
+    pub fn pop_layout(&mut self) -> Option<ActionLayout> {
+        self.layouts.pop()
+    }
+
+    /// 現在スタックに積まれているレイアウトの名前で検索し、該当するものを取り除く
+    pub fn pop_layout_named(&mut self, name: &str) -> Option<ActionLayout> {
+        let index = self.layouts.iter().rposition(|l| l.name == name)?;
+        Some(self.layouts.remove(index))
+    }
+
+    /// `InputState::update` の後に毎フレーム呼び出し、現在押されている入力からアクション値を
+    /// 再計算する。スタック上の全レイアウトのバインディングを合算し、アクション種別のレンジに
+    /// クランプする。
+    pub fn update(&mut self, input: &InputState) {
+        self.previous_values = std::mem::take(&mut self.values);
+
+        let mut next: HashMap<String, f32> =
+            self.actions.keys().map(|name| (name.clone(), 0.0)).collect();
+
+        for layout in &self.layouts {
+            for binding in &layout.bindings {
+                let is_active = match binding.source {
+                    ActionSource::Key(key) => input.keys_pressed.contains(&key),
+                    ActionSource::MouseButton(button) => input.mouse_buttons.contains(&button),
+                };
+                if !is_active {
+                    continue;
+                }
+                *next.entry(binding.action.clone()).or_insert(0.0) += binding.scale;
+            }
+        }
+
+        for (name, value) in next.iter_mut() {
+            let kind = self.actions.get(name).copied().unwrap_or(ActionKind::Axis);
+            *value = match kind {
+                ActionKind::Button => value.clamp(0.0, 1.0),
+                ActionKind::Axis => value.clamp(-1.0, 1.0),
+            };
+        }
+
+        self.values = next;
+    }
+
+    /// アクションの現在値を返す。未登録のアクションは 0.0。
+    pub fn action_value(&self, name: &str) -> f32 {
+        self.values.get(name).copied().unwrap_or(0.0)
+    }
+
+    /// このフレームでアクションが 0 から非0へ立ち上がったか（エッジ検出）
+    pub fn action_just_pressed(&self, name: &str) -> bool {
+        let now = self.action_value(name);
+        let prev = self.previous_values.get(name).copied().unwrap_or(0.0);
+        now != 0.0 && prev == 0.0
+    }
+}
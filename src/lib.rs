@@ -3,15 +3,19 @@ pub mod renderer;
 pub mod ecs;
 pub mod input;
 pub mod game;
+pub mod app;
 pub mod config;
 pub mod asset_manager;
 pub mod logger;
+pub mod render_graph;
 
 
 pub use renderer::Renderer;
 pub use ecs::World;
-pub use input::InputState;
+pub use input::{ActionHandler, InputEvent, InputState};
 pub use game::{run_game, Game};
+pub use app::{App, Plugin};
 pub use config::GameConfig;
 pub use asset_manager::AssetManager;
 pub use logger::init_logger_with_config;
+pub use render_graph::{RenderGraph, RenderTarget};
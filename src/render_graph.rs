@@ -0,0 +1,196 @@
+// src/render_graph.rs
+use std::collections::HashMap;
+
+use crate::ecs::World;
+
+/// ノードの描画先。`Surface` はその回のフレームで渡されたスワップチェインの `view`、
+/// `Named` は `RenderGraph` 自身が確保・管理する中間テクスチャを指す。
+pub enum RenderTarget {
+    Surface,
+    Named(String),
+}
+
+/// 1つのパスを表すノード。`inputs` に挙げた名前は、そのテクスチャを `output` として持つ
+/// 別のノードより後に実行されることを保証するためだけに使う（実際のサンプリングは
+/// `draw` クロージャ側がキャプチャした bind group 経由で行う）。
+struct RenderNode {
+    name: String,
+    output: RenderTarget,
+    inputs: Vec<String>,
+    clear_color: Option<wgpu::Color>,
+    draw: Box<dyn FnMut(&mut wgpu::RenderPass, &World)>,
+}
+
+/// 名前付き中間テクスチャ1枚分のGPUリソース。
+struct IntermediateTarget {
+    #[allow(dead_code)] // view が生きている間、テクスチャ本体を保持するためだけに持つ
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+/// ゲーム側がパスと中間ターゲットを宣言的に組み立てるための、軽量なレンダーグラフ。
+///
+/// `Game::build_graph` でノードを追加して構築する。各ノードの `inputs` / `output` の
+/// 依存関係からトポロジカルソートで実行順を決め、`execute` が1フレームぶんのノードを
+/// 順番にレンダーパスとして記録する。ノードを1つも追加しなければ `is_empty()` が true を
+/// 返し、`App` は従来どおり `Game::render` を直接呼ぶ（既存のシンプルなゲームはこの仕組みを
+/// 意識しなくてよい）。
+pub struct RenderGraph {
+    nodes: Vec<RenderNode>,
+    targets: HashMap<String, IntermediateTarget>,
+}
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new(), targets: HashMap::new() }
+    }
+
+    /// ノードを追加する。`inputs` には、このノードが読む中間テクスチャの名前（他ノードが
+    /// `output` に指定した `RenderTarget::Named` の名前）を渡す（実行順の解決にのみ使われる）。
+    pub fn add_node(
+        &mut self,
+        name: impl Into<String>,
+        output: RenderTarget,
+        inputs: &[&str],
+        clear_color: Option<wgpu::Color>,
+        draw: impl FnMut(&mut wgpu::RenderPass, &World) + 'static,
+    ) {
+        self.nodes.push(RenderNode {
+            name: name.into(),
+            output,
+            inputs: inputs.iter().map(|s| s.to_string()).collect(),
+            clear_color,
+            draw: Box::new(draw),
+        });
+    }
+
+    /// ノードが1つも追加されていないか（= `Game::build_graph` が空実装のまま）を返す。
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// ノードと中間テクスチャを空にする。`App` はリサイズ時の再構築で
+    /// これを呼んでから `Game::build_graph` を呼び直す（起動時は `new` した空のグラフを使う）。
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.targets.clear();
+    }
+
+    /// `RenderTarget::Named` を出力先に持つノードの分だけ、中間テクスチャを
+    /// `width` x `height`（論理解像度）で確保する。既にあるものは作り直さない。
+    pub fn ensure_targets(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) {
+        let names: Vec<String> = self
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.output {
+                RenderTarget::Named(name) => Some(name.clone()),
+                RenderTarget::Surface => None,
+            })
+            .collect();
+        for name in names {
+            self.targets.entry(name).or_insert_with(|| Self::create_target(device, format, width, height));
+        }
+    }
+
+    fn create_target(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> IntermediateTarget {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("RenderGraph Intermediate Target"),
+            size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        IntermediateTarget { texture, view }
+    }
+
+    /// 宣言された `inputs`（他ノードの `output` 名）をもとに、依存先を先に実行する順序へ
+    /// トポロジカルソートする（カーンのアルゴリズム）。循環があれば `build_graph` の実装ミスなので
+    /// パニックで知らせる。
+    fn resolve_order(&self) -> Vec<usize> {
+        let producer_of: HashMap<&str, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| match &node.output {
+                RenderTarget::Named(name) => Some((name.as_str(), i)),
+                RenderTarget::Surface => None,
+            })
+            .collect();
+
+        let mut remaining_deps: Vec<usize> = self
+            .nodes
+            .iter()
+            .map(|node| node.inputs.iter().filter(|input| producer_of.contains_key(input.as_str())).count())
+            .collect();
+
+        let mut ready: Vec<usize> = remaining_deps
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &count)| if count == 0 { Some(i) } else { None })
+            .collect();
+
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(i) = ready.pop() {
+            order.push(i);
+            for (j, node) in self.nodes.iter().enumerate() {
+                if order.contains(&j) || ready.contains(&j) {
+                    continue;
+                }
+                let depends_on_i = node.inputs.iter().any(|input| producer_of.get(input.as_str()) == Some(&i));
+                if depends_on_i {
+                    remaining_deps[j] -= 1;
+                    if remaining_deps[j] == 0 {
+                        ready.push(j);
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            order.len(),
+            self.nodes.len(),
+            "RenderGraph にサイクルがあります（ノードの inputs/output を確認してください）"
+        );
+        order
+    }
+
+    /// 解決した実行順で各ノードのレンダーパスを開き、描画コマンドの記録をクロージャへ委ねる。
+    /// ノードごとに1回 `begin_render_pass` する素直な逐次実行で、パスの合成（MSAA 解決など）は
+    /// 行わない。深度バッファは今のところ持たず、カラーのみを対象にする。
+    pub fn execute(&mut self, encoder: &mut wgpu::CommandEncoder, surface_view: &wgpu::TextureView, world: &World) {
+        let order = self.resolve_order();
+        let RenderGraph { nodes, targets, .. } = self;
+        for i in order {
+            let node = &mut nodes[i];
+            let view = match &node.output {
+                RenderTarget::Surface => surface_view,
+                RenderTarget::Named(name) => {
+                    &targets
+                        .get(name)
+                        .unwrap_or_else(|| panic!("RenderGraph: 中間テクスチャ '{}' が未確保です（ensure_targets を呼び忘れていませんか）", name))
+                        .view
+                }
+            };
+
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(node.name.as_str()),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: match node.clear_color {
+                            Some(color) => wgpu::LoadOp::Clear(color),
+                            None => wgpu::LoadOp::Load,
+                        },
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            (node.draw)(&mut pass, world);
+        }
+    }
+}
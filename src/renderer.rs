@@ -1,733 +1,2769 @@
-// src/renderer.rs
-
-use std::collections::HashMap;
-use std::cell::RefCell;
-use std::rc::Rc;
-
-use futures::channel::oneshot;
-use std::time::Instant;
-
-use pollster;
-
-use wgpu::util::DeviceExt;
-use winit::window::Window;
-
-use crate::ecs::World;
-use crate::GameConfig;
-
-/// 描画エンジンの中心構造体。WGPU の初期化、描画処理、リソース管理などを担当する。
-pub struct Renderer {
-    pub device: wgpu::Device,
-    pub queue: wgpu::Queue,
-    pub surface: wgpu::Surface,
-    pub config: wgpu::SurfaceConfiguration,
-    pub surface_format: wgpu::TextureFormat,
-
-    // テクスチャ描画用のリソース（シェーダー、パイプラインなど）
-    texture_pipeline: wgpu::RenderPipeline,
-    texture_bind_group_layout: wgpu::BindGroupLayout,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-
-    // ユニフォーム用のバッファとバインドグループ
-    uniform_buffer: wgpu::Buffer,
-    uniform_bind_group: wgpu::BindGroup,
-
-    //テクスチャ用 bind group のキャッシュ
-    texture_bind_group_cache: RefCell<HashMap<*const TextureHandle, Rc<wgpu::BindGroup>>>,
-
-    //ダブルバッファ用の頂点バッファとインデックスバッファ、バッファ切り替え用のインデックス
-    pub batched_vertex_buffers: [wgpu::Buffer; 2],
-    pub batched_index_buffers: [wgpu::Buffer; 2],
-    pub current_buffer: usize,
-
-    pub batched_vertex_buffer: wgpu::Buffer,
-    pub batched_index_buffer: wgpu::Buffer,
-}
-
-/// テクスチャとサンプラーをまとめた構造体。
-/// テクスチャ本体も保持することで、ビューが無効にならないようにする。
-#[derive(Debug)]
-pub struct TextureHandle {
-    pub texture: wgpu::Texture,  // 追加: テクスチャ本体を保持
-    pub view: wgpu::TextureView,
-    pub sampler: wgpu::Sampler,
-}
-
-
-impl Renderer {
-    /// Renderer構造体の初期化。
-    /// ウィンドウと連携し、WGPUの初期化・パイプライン・バインドレイアウトをセットアップする。
-    pub async fn new(window: &Window) -> Self {
-        use wgpu::util::DeviceExt;
-
-        // ウィンドウサイズ取得（物理サイズ）
-        let size = window.inner_size();
-
-        // 固定の論理サイズ
-        let logical_width: f32 = 800.0;
-        let logical_height: f32 = 600.0;
-
-        // WGPUインスタンスとサーフェス作成
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
-        let surface = unsafe { instance.create_surface(window) }.unwrap();
-
-        // アダプター取得
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            compatible_surface: Some(&surface),
-            ..Default::default()
-        }).await.unwrap();
-
-        // デバイスとキューの作成
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default(), None).await.unwrap();
-
-        // サーフェスのフォーマットと設定
-        let surface_format = surface.get_capabilities(&adapter).formats[0];
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
-            view_formats: vec![surface_format],
-        };
-        surface.configure(&device, &config);
-
-        // バインドグループレイアウト（group 0: uniforms）
-        let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Uniform BindGroup Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-        // 固定の論理サイズから uniform のスケール値を計算
-        // (論理座標 (0,0)-(800,600) を NDC (-1,-1)-(1,1) に変換する)
-        let uniform_data: [f32; 2] = [2.0 / logical_width, 2.0 / logical_height];
-        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform Buffer"),
-            contents: bytemuck::cast_slice(&uniform_data),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Uniform BindGroup"),
-            layout: &uniform_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-        });
-
-        // 次に、group 1: texture + sampler のレイアウトを作成
-        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Texture BindGroup Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-        });
-
-        // シェーダー読み込み
-        let shader_src = std::fs::read_to_string("assets/shader_texture.wgsl").unwrap();
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Texture Shader"),
-            source: wgpu::ShaderSource::Wgsl(shader_src.into()),
-        });
-
-        // パイプラインレイアウト（2つのbind group layoutを指定）
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Texture Pipeline Layout"),
-            bind_group_layouts: &[
-                &uniform_bind_group_layout,
-                &texture_bind_group_layout,
-            ],
-            push_constant_ranges: &[],
-        });
-
-        // テクスチャ描画用のパイプライン作成
-        let texture_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Texture Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &[
-                        wgpu::VertexAttribute {
-                            offset: 0,
-                            shader_location: 0,
-                            format: wgpu::VertexFormat::Float32x2,
-                        },
-                        wgpu::VertexAttribute {
-                            offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
-                            shader_location: 1,
-                            format: wgpu::VertexFormat::Float32x2,
-                        },
-                    ],
-                }],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-        });
-
-        // インデックスバッファ（四角形）
-        let index_data: [u16; 6] = [0, 1, 2, 2, 3, 0];
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(&index_data),
-            usage: wgpu::BufferUsages::INDEX,
-        });
-
-        // ダミー頂点バッファ（必要に応じて draw 時に書き換える）
-        let vertex_data: [[f32; 4]; 4] = [[0.0; 4]; 4];
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertex_data),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-        });
-
-        // 新たにダブルバッファを初期化（サイズは例として 4096 バイト）
-        let batched_vertex_buffer_0 = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Batched Vertex Buffer 0"),
-            size: 4096,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let batched_vertex_buffer_1 = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Batched Vertex Buffer 1"),
-            size: 4096,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let batched_index_buffer_0 = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Batched Index Buffer 0"),
-            size: 4096,
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let batched_index_buffer_1 = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Batched Index Buffer 1"),
-            size: 4096,
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        // AssetManager のキャッシュやその他のフィールドも初期化
-        let texture_bind_group_cache = std::cell::RefCell::new(HashMap::new());
-
-        let batched_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Batched Vertex Buffer"),
-            size: 128 * 1024, // 128KB
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let batched_index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Batched Index Buffer"),
-            size: 32 * 1024, // 32KB
-            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        // 構造体の生成・返却
-        Self {
-            device,
-            queue,
-            surface,
-            config,
-            surface_format,
-            texture_pipeline,
-            texture_bind_group_layout,
-            vertex_buffer,
-            index_buffer,
-            uniform_buffer,
-            uniform_bind_group,
-            texture_bind_group_cache,
-
-            batched_vertex_buffers: [batched_vertex_buffer_0, batched_vertex_buffer_1],
-            batched_index_buffers: [batched_index_buffer_0, batched_index_buffer_1],
-            current_buffer: 0,
-
-            batched_vertex_buffer,
-            batched_index_buffer
-        }
-    }
-
-    /// 指定したスケール値で uniform_buffer を更新します。
-    pub fn update_uniform(&self, scale: &[f32; 2]) {
-        self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(scale));
-    }
-
-    /// ウィンドウサイズが変更されたときの処理。
-    /// 新しい物理サイズでサーフェスを再構成し、stretch_mode に応じて uniform_buffer を更新する。
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>, config: &GameConfig) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-
-            // stretch_mode の値によって、uniform のスケールを決定する
-            let scale = if config.stretch_mode {
-                // ウィンドウの物理サイズに合わせる
-                [2.0 / new_size.width as f32, 2.0 / new_size.height as f32]
-            } else {
-                // 論理解像度を固定（config.logical_width, config.logical_height に基づく）
-                [2.0 / config.logical_width as f32, 2.0 / config.logical_height as f32]
-            };
-            self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&scale));
-        }
-    }
-
-    pub fn render(&mut self, world: &crate::ecs::World) {
-        log::debug!(target: "rendering", "=== Starting render() ===");
-        let output = match self.surface.get_current_texture() {
-            Ok(tex) => tex,
-            Err(_) => {
-                self.surface.configure(&self.device, &self.config);
-                self.surface.get_current_texture().expect("Failed to reacquire surface texture")
-            }
-        };
-        
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-        
-        self.draw_sprites_batched(&mut encoder, &view, world);
-        
-        self.queue.submit(Some(encoder.finish()));
-        output.present();
-        
-        let sync_start = std::time::Instant::now();
-        let (sender, receiver) = futures::channel::oneshot::channel();
-        self.queue.on_submitted_work_done(move || {
-            let _ = sender.send(());
-        });
-        pollster::block_on(receiver).unwrap();
-        let sync_duration = sync_start.elapsed();
-        log::debug!(target: "rendering", "GPU synchronization complete in {:?}", sync_duration);
-        
-        log::debug!(target: "rendering", "Before switching, current_buffer = {}", self.current_buffer);
-        self.current_buffer = (self.current_buffer + 1) % self.batched_vertex_buffers.len();
-        log::debug!(target: "rendering", "Switched current_buffer to {}", self.current_buffer);
-    }
-
-    /// テクスチャを読み込み、GPUへ転送して TextureHandle を返す。
-    /// 
-    /// # 引数
-    /// * `path` - 画像ファイルのパス
-    ///
-    /// # 戻り値
-    /// * `TextureHandle` - view + sampler を含む構造体
-    pub fn load_texture(&self, path: &str) -> TextureHandle {
-        use image::GenericImageView;
-    
-        let img = image::open(path).expect("Failed to open image").to_rgba8();
-        let (width, height) = img.dimensions();
-        let size = wgpu::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        };
-    
-        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("User Texture"),
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
-    
-        self.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &img,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width),
-                rows_per_image: Some(height),
-            },
-            size,
-        );
-    
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
-    
-        TextureHandle {
-            texture, // テクスチャ本体を保持する
-            view,
-            sampler,
-        }
-    }
-    
-
-    /// 指定したテクスチャを、指定した領域に描画する。
-    ///
-    /// # 引数
-    /// * `encoder` - コマンドエンコーダ
-    /// * `view` - 描画対象のテクスチャビュー
-    /// * `texture` - 描画対象のテクスチャ（ハンドル）
-    /// * `x`, `y`, `w`, `h` - 描画する矩形の左下座標とサイズ（論理座標）
-    pub fn draw_texture(
-        &self,
-        encoder: &mut wgpu::CommandEncoder,
-        view: &wgpu::TextureView,
-        texture: &TextureHandle,
-        x: f32,
-        y: f32,
-        w: f32,
-        h: f32,
-    ) {
-        // ここでは論理座標系（0,0)-(800,600) を前提とするので、
-        // 頂点データはそのまま論理座標で渡す
-        let vertex_data = [
-            [x, y + h, 0.0, 0.0],     // 左上
-            [x + w, y + h, 1.0, 0.0],   // 右上
-            [x + w, y, 1.0, 1.0],       // 右下
-            [x, y, 0.0, 1.0],           // 左下
-        ];
-    
-        self.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertex_data));
-    
-        // テクスチャ用 bind group を作成（group 1）
-        let texture_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &self.texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
-                },
-            ],
-            label: Some("Texture BindGroup"),
-        });
-    
-        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Texture Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
-    
-        // パイプラインを最初にセットする
-        pass.set_pipeline(&self.texture_pipeline);
-    
-        // シェーダーのバインド順に合わせる
-        pass.set_bind_group(0, &self.uniform_bind_group, &[]); // ユニフォーム（group 0）
-        pass.set_bind_group(1, &texture_bind_group, &[]);        // テクスチャ＋サンプラー（group 1）
-    
-        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        pass.draw_indexed(0..6, 0, 0..1);
-    }
-
-    /// World 内のエンティティをすべて描画する。
-    /// ここでは、各エンティティごとに新しい bind group を作成し、ローカルなベクターに保持してから描画します。
-    pub fn draw_world(
-        &self,
-        encoder: &mut wgpu::CommandEncoder,
-        view: &wgpu::TextureView,
-        world: &crate::ecs::World,
-    ) {
-        // 各エンティティごとのリソースを保持するベクターを用意する
-        let mut entity_vertex_buffers: Vec<wgpu::Buffer> = Vec::new();
-        let mut entity_bind_groups: Vec<wgpu::BindGroup> = Vec::new();
-        let mut transforms: Vec<crate::ecs::Transform> = Vec::new();
-    
-        // すべての描画対象エンティティについて、各リソースを生成して保持する
-        for (transform, texture) in world.query_drawables() {
-            transforms.push(transform);
-            // 論理座標系 (0,0)-(800,600) を前提とする頂点データ
-            let vertex_data = [
-                [transform.x, transform.y + transform.h, 0.0, 0.0],     // 左上
-                [transform.x + transform.w, transform.y + transform.h, 1.0, 0.0], // 右上
-                [transform.x + transform.w, transform.y, 1.0, 1.0],       // 右下
-                [transform.x, transform.y, 0.0, 1.0],                     // 左下
-            ];
-            let vb = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Entity Vertex Buffer"),
-                contents: bytemuck::cast_slice(&vertex_data),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
-            entity_vertex_buffers.push(vb);
-    
-            let bg = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &self.texture_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&texture.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&texture.sampler),
-                    },
-                ],
-                label: Some("Entity Texture BindGroup"),
-            });
-            entity_bind_groups.push(bg);
-        }
-
-        // draw_world 内のループでテクスチャ情報を出力（比較用）
-        for (i, (_transform, texture)) in world.query_drawables().iter().enumerate() {
-            log::debug!(target: "rendering", "draw_world Entity {}: texture_ptr = {:p}", i, texture);
-        }
-    
-        // レンダーパスを一度だけ開始する
-        {
-            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("World Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            });
-    
-            pass.set_pipeline(&self.texture_pipeline);
-            // ユニフォームは共通
-            pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-    
-            // 各エンティティごとに描画コマンドを記録する
-            for (i, _transform) in transforms.iter().enumerate() {
-                pass.set_bind_group(1, &entity_bind_groups[i], &[]);
-                pass.set_vertex_buffer(0, entity_vertex_buffers[i].slice(..));
-                pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                pass.draw_indexed(0..6, 0, 0..1);
-            }
-        }
-        // レンダーパス終了後、上記ベクターに保持していたリソースは drop されますが、
-        // コマンドバッファには既に記録されているので問題ありません。
-    }
-    
-     /// draw_sprites_batched は、World 内のエンティティ（Transform と Rc<TextureHandle> のペア）
-    /// をテクスチャごとにグループ化し、事前確保されたダブルバッファに頂点・インデックスデータを書き込み
-    /// そのオフセット情報をもとに一括描画します。各グループの内部状態を詳細にログ出力します。
-    pub fn draw_sprites_batched(
-        &mut self,
-        encoder: &mut wgpu::CommandEncoder,
-        view: &wgpu::TextureView,
-        world: &crate::ecs::World,
-    ) {
-        use std::time::Instant;
-        let t0 = Instant::now();
-        log::debug!(target: "rendering", "=== Starting draw_sprites_batched ===");
-    
-        // (1) Query and sort drawables
-        let mut drawables = world.query_drawables_with_z();
-        drawables.sort_by(|(a, _), (b, _)| a.z.partial_cmp(&b.z).unwrap());
-        log::debug!(target: "rendering", "Sorted drawables by z. Total drawables: {}", drawables.len());
-        for (i, (transform, _)) in drawables.iter().enumerate() {
-            log::debug!(target: "rendering", "Drawable {}: pos=({:.2},{:.2}), size=({:.2},{:.2}), z={:.2}",
-                i, transform.x, transform.y, transform.w, transform.h, transform.z);
-        }
-    
-        // (2) Batch creation
-        #[derive(Debug)]
-        struct Batch {
-            texture_ptr: usize,
-            drawables: Vec<(crate::ecs::Transform, std::rc::Rc<crate::renderer::TextureHandle>)>,
-        }
-        let mut batches: Vec<Batch> = Vec::new();
-        for drawable in drawables {
-            let key = std::rc::Rc::as_ptr(&drawable.1) as usize;
-            if let Some(last) = batches.last_mut() {
-                if last.texture_ptr == key {
-                    last.drawables.push(drawable);
-                    continue;
-                }
-            }
-            batches.push(Batch {
-                texture_ptr: key,
-                drawables: vec![drawable],
-            });
-        }
-        log::debug!(target: "rendering", "Created {} batches", batches.len());
-        // 各バッチの z 値範囲を出力
-        for (i, batch) in batches.iter().enumerate() {
-            let mut z_min = std::f32::MAX;
-            let mut z_max = std::f32::MIN;
-            for (transform, _) in &batch.drawables {
-                if transform.z < z_min { z_min = transform.z; }
-                if transform.z > z_max { z_max = transform.z; }
-            }
-            log::debug!(target: "rendering", "Batch {}: texture_ptr = {:p}, drawables_count = {}, z range = [{:.2}, {:.2}]",
-                i, batch.drawables[0].1, batch.drawables.len(), z_min, z_max);
-        }
-    
-        // (3) Aggregation: Build global vertex and index buffers from batches
-        let mut global_vertices: Vec<[f32; 4]> = Vec::new();
-        let mut global_indices: Vec<u16> = Vec::new();
-    
-        struct BatchDrawCall {
-            texture_bg: wgpu::BindGroup,
-            vertex_offset: u64,
-            vertex_count: u32,
-            index_offset: u64,
-            index_count: u32,
-        }
-        let mut draw_calls = Vec::new();
-        let mut vertex_count_total: u16 = 0;
-    
-        for batch in batches {
-            let texture_bg = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &self.texture_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&batch.drawables[0].1.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&batch.drawables[0].1.sampler),
-                    },
-                ],
-                label: Some("Batched Texture BindGroup"),
-            });
-            let batch_vertex_offset_elements = global_vertices.len();
-            for (transform, _) in &batch.drawables {
-                // top-left, top-right, bottom-right, bottom-left
-                global_vertices.push([transform.x, transform.y + transform.h, 0.0, 0.0]);
-                global_vertices.push([transform.x + transform.w, transform.y + transform.h, 1.0, 0.0]);
-                global_vertices.push([transform.x + transform.w, transform.y, 1.0, 1.0]);
-                global_vertices.push([transform.x, transform.y, 0.0, 1.0]);
-    
-                global_indices.push(vertex_count_total);
-                global_indices.push(vertex_count_total + 1);
-                global_indices.push(vertex_count_total + 2);
-                global_indices.push(vertex_count_total + 2);
-                global_indices.push(vertex_count_total + 3);
-                global_indices.push(vertex_count_total);
-                vertex_count_total += 4;
-            }
-            let vertex_offset_bytes = (batch_vertex_offset_elements * std::mem::size_of::<[f32; 4]>()) as u64;
-            let batch_index_offset_elements = global_indices.len() - (batch.drawables.len() * 6);
-            let index_offset_bytes = (batch_index_offset_elements * std::mem::size_of::<u16>()) as u64;
-            let batch_vertex_count = (batch.drawables.len() * 4) as u32;
-            let batch_index_count = (batch.drawables.len() * 6) as u32;
-    
-            draw_calls.push(BatchDrawCall {
-                texture_bg,
-                vertex_offset: vertex_offset_bytes,
-                vertex_count: batch_vertex_count,
-                index_offset: index_offset_bytes,
-                index_count: batch_index_count,
-            });
-        }
-    
-        log::debug!(target: "rendering", "Aggregated vertices count: {}, indices count: {}", global_vertices.len(), global_indices.len());
-        log::debug!(target: "rendering", "Aggregated vertices (first 8): {:?}", &global_vertices.iter().take(8).collect::<Vec<_>>());
-        if global_vertices.len() > 8 {
-            log::debug!(target: "rendering", "Aggregated vertices (last 4): {:?}", &global_vertices[global_vertices.len()-4..]);
-        }
-        log::debug!(target: "rendering", "Aggregated indices: {:?}", global_indices);
-    
-        // (4) Buffer write and current_buffer check
-        log::debug!(target: "rendering", "Before buffer write, current_buffer = {}", self.current_buffer);
-        self.queue.write_buffer(&self.batched_vertex_buffers[self.current_buffer], 0, bytemuck::cast_slice(&global_vertices));
-        self.queue.write_buffer(&self.batched_index_buffers[self.current_buffer], 0, bytemuck::cast_slice(&global_indices));
-    
-        // (5) Render pass and draw calls
-        // ※必要なら、Renderer 側で保持している uniform の scale 値もここでログ出力してください。
-        let t_render = Instant::now();
-        {
-            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Batched Sprite Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            });
-            pass.set_pipeline(&self.texture_pipeline);
-            pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-            for (i, dc) in draw_calls.iter().enumerate() {
-                let vertex_range = dc.vertex_offset..(dc.vertex_offset + dc.vertex_count as u64 * std::mem::size_of::<[f32; 4]>() as u64);
-                let index_range = dc.index_offset..(dc.index_offset + dc.index_count as u64 * std::mem::size_of::<u16>() as u64);
-                log::debug!(target: "rendering", "Batch draw {}: vertex_range = {:?}, index_range = {:?}, texture BG = {:?}", 
-                    i, vertex_range, index_range, dc.texture_bg);
-                pass.set_bind_group(1, &dc.texture_bg, &[]);
-                pass.set_vertex_buffer(0, self.batched_vertex_buffers[self.current_buffer].slice(vertex_range));
-                pass.set_index_buffer(self.batched_index_buffers[self.current_buffer].slice(index_range), wgpu::IndexFormat::Uint16);
-                pass.draw_indexed(0..dc.index_count, 0, 0..1);
-                log::debug!(target: "rendering", "Draw call for batch {} executed, index_count = {}", i, dc.index_count);
-            }
-        }
-        log::debug!(target: "rendering", "Render pass complete in {:?}", t_render.elapsed());
-        log::debug!(target: "rendering", "Total batched draw time: {:?}", t0.elapsed());
-        log::debug!(target: "rendering", "=== End of draw_sprites_batched ===");
-    }
-    
-    
-    
-    
-    
-    
-        
-
-}
+// src/renderer.rs
+
+use std::collections::HashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use std::time::Instant;
+
+use fontdue::{Font, FontSettings};
+use wgpu::util::DeviceExt;
+use winit::window::Window;
+
+use crate::ecs::World;
+use crate::GameConfig;
+
+/// 描画エンジンの中心構造体。WGPU の初期化、描画処理、リソース管理などを担当する。
+pub struct Renderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub surface: wgpu::Surface,
+    pub config: wgpu::SurfaceConfiguration,
+    pub surface_format: wgpu::TextureFormat,
+
+    // テクスチャ描画用のリソース（シェーダー、パイプラインなど）
+    texture_pipeline: wgpu::RenderPipeline,
+    // `draw_model` 用の3Dメッシュ描画パイプライン。bind group layout は texture_pipeline と
+    // 共有する（group 0: camera uniform、group 1: 拡散テクスチャ+サンプラー）。
+    model_pipeline: wgpu::RenderPipeline,
+    // `draw_mesh` 用の、頂点ごとの RGB 頂点色つき2Dメッシュ描画パイプライン。
+    mesh_color_pipeline: wgpu::RenderPipeline,
+    // インスタンシング描画用パイプライン。ユニットクアッドをインスタンスごとに
+    // 平行移動・スケールするので、テクスチャ1つにつき bind group 1回・draw call 1回で済む。
+    // BlendMode ごとに色合成だけ異なるバリアントをキャッシュし、バッチの blend_mode で選択する。
+    instanced_pipelines: HashMap<BlendMode, wgpu::RenderPipeline>,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    vertex_buffer: wgpu::Buffer,
+    // (0,0)-(1,1) のユニットクアッド。インスタンシング描画での頂点バッファ（step_mode: Vertex）。
+    unit_quad_vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+
+    // ユニフォーム用のバッファとバインドグループ。view_proj は camera から毎フレーム書き込む。
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    camera: Camera2D,
+
+    //テクスチャ用 bind group のキャッシュ
+    texture_bind_group_cache: RefCell<HashMap<*const TextureHandle, Rc<wgpu::BindGroup>>>,
+
+    //ダブルバッファ用の頂点バッファとインデックスバッファ、バッファ切り替え用のインデックス
+    //draw_world のインスタンシング描画では、これをインスタンスデータ（x,y,w,h,z,色変換,UV範囲）用バッファとして使う
+    pub batched_vertex_buffers: [wgpu::Buffer; 2],
+    pub batched_index_buffers: [wgpu::Buffer; 2],
+    pub current_buffer: usize,
+    // batched_vertex_buffers の各バッファの現在のバイト容量。必要なインスタンス数が増えたら
+    // バッファを作り直して拡張する（ダブルバッファの構造はそのまま）。
+    batched_vertex_buffer_capacity: [u64; 2],
+
+    pub batched_index_buffer: wgpu::Buffer,
+
+    // draw_sprites_batched_cached のバッチ構成（テクスチャとインスタンス数の並び）が前回と
+    // 変わらないレイヤーについて、draw call の再記録を省略して使い回す RenderBundle。
+    // layer_id ごとに専用のインスタンスバッファとバンドルを保持するレジストリで、同じ
+    // フレーム内で複数の layer_id を描画しても互いのバッファ/バンドルを上書きしない
+    // （batched_vertex_buffers のような単一の共有バッファにすると、レイヤー B の再構築が
+    // レイヤー A のバンドルが参照するデータまで書き換えてしまう）。シグネチャ（バッチごとの
+    // (テクスチャ, blend_mode, インスタンス数) の並び）が前回と変わったレイヤーだけ作り直す。
+    sprite_bundle_cache: HashMap<u64, CachedSpriteLayer>,
+
+    // transform.z による描画順をハードウェアの深度テストに任せるための深度バッファ。
+    // リサイズのたびにサーフェスと同じサイズへ作り直す。
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+
+    // instanced_pipeline (draw_world / draw_sprites_batched) 用の MSAA カラーバッファと
+    // それに対応する深度バッファ。サンプル数はカラー・深度・パイプラインの3者で一致させる必要がある。
+    msaa_framebuffer: wgpu::Texture,
+    msaa_framebuffer_view: wgpu::TextureView,
+    msaa_depth_texture: wgpu::Texture,
+    msaa_depth_view: wgpu::TextureView,
+
+    // sprites の描画先（＝ポストプロセスチェインへの "source"）。ロジカル解像度で作り、
+    // resize() で GameConfig の論理解像度が現在のサイズと異なれば作り直す。チェインの各パスはここから読み出し、
+    // `scale` に応じたサイズの自前テクスチャ（`PostProcessPass::output`）へ書き込んでいく。
+    // 最終パスのみスワップチェインのビューへ直接書き込むので、これと同じフォーマット
+    // （surface_format）で作る。
+    post_process_targets: [wgpu::Texture; 1],
+    post_process_target_views: [wgpu::TextureView; 1],
+    post_process_sampler: wgpu::Sampler,
+    post_process_vertex_shader: wgpu::ShaderModule,
+    post_process_bind_group_layout: wgpu::BindGroupLayout,
+    post_process_pipeline_layout: wgpu::PipelineLayout,
+    // 実行中のポストプロセスチェイン。既定では何もしないパススルー1パスのみを積んでおき、
+    // `load_post_process_chain` でプリセットファイルから読み込んだパス列に差し替える。
+    post_process_passes: Vec<PostProcessPass>,
+    frame_counter: u64,
+    start_time: Instant,
+
+    // stretch_mode が false のとき、最終ブリットパスをサーフェス中央にアスペクト比を保って
+    // レターボックス表示するための矩形。resize() で GameConfig の論理解像度・stretch_mode と
+    // 新しいサーフェスサイズから作り直す。
+    letterbox_viewport: LetterboxViewport,
+
+    // テキスト描画用。`font` でグリフをラスタライズし、`glyph_atlas` にキャッシュする。
+    // `draw_text` / `draw_world` はどちらもここへ描画データを積んで同じパイプラインで描く。
+    font: Font,
+    glyph_atlas: GlyphAtlas,
+    text_pipeline: wgpu::RenderPipeline,
+    text_pipeline_msaa: wgpu::RenderPipeline,
+    text_vertex_buffer: wgpu::Buffer,
+    text_index_buffer: wgpu::Buffer,
+    text_vertex_buffer_capacity: u64,
+    text_index_buffer_capacity: u64,
+    // `queue_text` で積まれた、Entity を経由しない使い捨てのテキスト。`draw_world` が
+    // ECS の Text エンティティと合わせて描画し、描画し終えたら空にする。
+    pending_texts: Vec<PendingText>,
+}
+
+/// `Renderer::draw_sprites_batched_cached` が layer_id ごとに保持する構築済みバンドル。
+/// `instance_buffer` はそのレイヤー専用で、他のレイヤーと共有しない（再構築のたびに
+/// 作り直すので、バンドルが参照するバッファハンドルは常に有効）。
+struct CachedSpriteLayer {
+    signature: Vec<(usize, BlendMode, u32)>,
+    instance_buffer: wgpu::Buffer,
+    bundle: wgpu::RenderBundle,
+}
+
+/// `Renderer::queue_text` が積む、次の `draw_world` で描画される1行分のテキスト。
+/// 位置は論理座標系のベースライン（`ecs::Text` と同じ解釈）。
+struct PendingText {
+    content: String,
+    x: f32,
+    y: f32,
+    size: f32,
+    color: [f32; 4],
+}
+
+/// 最終ブリットパスで `RenderPass::set_viewport` に渡す矩形（物理ピクセル単位）。
+/// stretch_mode のときはサーフェス全体、そうでなければ中央寄せのレターボックス矩形になる。
+#[derive(Clone, Copy, Debug)]
+struct LetterboxViewport {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// サーフェスの物理サイズと論理解像度から、アスペクト比を保ったまま中央寄せする
+/// レターボックス矩形を計算する。stretch_mode が true の場合はサーフェス全体を返す。
+fn compute_letterbox_viewport(
+    surface_width: u32,
+    surface_height: u32,
+    logical_width: u32,
+    logical_height: u32,
+    stretch_mode: bool,
+) -> LetterboxViewport {
+    if stretch_mode || logical_width == 0 || logical_height == 0 {
+        return LetterboxViewport {
+            x: 0.0,
+            y: 0.0,
+            width: surface_width as f32,
+            height: surface_height as f32,
+        };
+    }
+
+    let surface_aspect = surface_width as f32 / surface_height as f32;
+    let logical_aspect = logical_width as f32 / logical_height as f32;
+
+    let (width, height) = if surface_aspect > logical_aspect {
+        // サーフェスの方が横長 → 高さいっぱいに合わせ、左右に黒帯
+        let height = surface_height as f32;
+        let width = height * logical_aspect;
+        (width, height)
+    } else {
+        // サーフェスの方が縦長（または同じ）→ 幅いっぱいに合わせ、上下に黒帯
+        let width = surface_width as f32;
+        let height = width / logical_aspect;
+        (width, height)
+    };
+
+    let x = (surface_width as f32 - width) / 2.0;
+    let y = (surface_height as f32 - height) / 2.0;
+
+    LetterboxViewport { x, y, width, height }
+}
+
+/// ワールド座標をクリップ空間へ写す2Dカメラ。`position` はビューポートの左下隅に写る
+/// ワールド座標（`Transform`/`draw_texture` と同じ、左下原点の座標系）で、センター基準の
+/// 「注視点」カメラではない。`position=(0,0), zoom=1.0, rotation=0.0` のとき、
+/// カメラ導入以前の `scale` のみによる変換（`p * scale - 1.0`）と完全に一致するので、
+/// 既存のスプライト・テキストの座標は camera を生やしただけでは何も変わらない。
+#[derive(Clone, Copy, Debug)]
+pub struct Camera2D {
+    pub position: [f32; 2],
+    pub zoom: f32,
+    pub rotation: f32,
+    // 論理解像度から決まる基準スケール（NDC 1単位あたりの論理ピクセル数の逆数 * 2）。
+    // viewport のサイズが変わったときだけ set_viewport で更新する。
+    base_scale: [f32; 2],
+}
+
+impl Camera2D {
+    /// 論理解像度 `viewport_width` x `viewport_height` を基準にカメラを作る。
+    /// 既定の position/zoom/rotation では、これまでの `scale` のみの変換と一致する。
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        Self {
+            position: [0.0, 0.0],
+            zoom: 1.0,
+            rotation: 0.0,
+            base_scale: [2.0 / viewport_width, 2.0 / viewport_height],
+        }
+    }
+
+    /// ビューポートの論理サイズが変わったときに基準スケールを作り直す。
+    pub fn set_viewport(&mut self, viewport_width: f32, viewport_height: f32) {
+        self.base_scale = [2.0 / viewport_width, 2.0 / viewport_height];
+    }
+
+    /// カメラの位置（ビューポート左下隅に写るワールド座標）を直接指定する。
+    pub fn set_position(&mut self, x: f32, y: f32) {
+        self.position = [x, y];
+    }
+
+    /// 現在のズーム値に `factor` を掛ける（1.0 より大きいと拡大、小さいと縮小）。
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom *= factor;
+    }
+
+    /// 頂点シェーダーへ渡す view-projection 行列。WGSL の `mat4x4<f32>` と同じ列優先の
+    /// メモリレイアウトで、16要素のフラットな配列として返す。
+    pub fn view_proj(&self) -> [f32; 16] {
+        let sx = self.zoom * self.base_scale[0];
+        let sy = self.zoom * self.base_scale[1];
+        let (sr, cr) = self.rotation.sin_cos();
+        let [px, py] = self.position;
+        let tx = -sx * cr * px - sx * sr * py - 1.0;
+        let ty = sy * sr * px - sy * cr * py - 1.0;
+        [
+            sx * cr, -sy * sr, 0.0, 0.0,
+            sx * sr, sy * cr, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            tx, ty, 0.0, 1.0,
+        ]
+    }
+}
+
+/// ポストプロセスの1パスを表す記述。プリセットファイルのパース結果として得られる。
+pub struct PassDesc {
+    pub shader_path: String,
+    /// 出力テクスチャをロジカル解像度の何倍で確保するか（既定 1.0）。
+    pub scale: f32,
+}
+
+/// 構築済みのポストプロセス1パス分の GPU リソース。
+struct PostProcessPass {
+    shader_path: String,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    scale: f32,
+    /// このパスの出力先。最後のパスはスワップチェインへ直接書くので `None`。
+    /// それ以外は `scale` に応じたサイズの自前テクスチャを持つ。
+    output: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+/// 深度バッファのフォーマット。全パイプラインの `depth_stencil` もこれに合わせる。
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// インスタンシング描画パイプラインのマルチサンプル数（MSAA）。
+const SAMPLE_COUNT: u32 = 4;
+
+/// `draw_text` / `draw_world` が使うフォントファイル。今のところ差し替え手段はなく、
+/// ゲーム側はこのパスにフォントを置く必要がある。
+const DEFAULT_FONT_PATH: &str = "assets/fonts/default.ttf";
+
+/// グリフアトラスの初期サイズ（正方形、ピクセル単位）。枠が足りなくなったら2倍に育てる。
+const GLYPH_ATLAS_INITIAL_SIZE: u32 = 512;
+
+/// テクスチャとサンプラーをまとめた構造体。
+/// テクスチャ本体も保持することで、ビューが無効にならないようにする。
+#[derive(Debug)]
+pub struct TextureHandle {
+    pub texture: wgpu::Texture,  // 追加: テクスチャ本体を保持
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+/// テクスチャ内の矩形範囲をピクセル単位で表す。`AssetManager::load_atlas` に渡すフレーム
+/// 定義に使う。
+#[derive(Clone, Copy, Debug)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// テクスチャアトラス内の1フレームを指す軽量ハンドル。アトラス全体の `Rc<TextureHandle>` を
+/// 他のフレームと共有し、フレームごとに異なるのは `uv_min`/`uv_max`（`GlyphInfo` と同じく
+/// アトラス全体に対する正規化座標）だけ。同じアトラスを使う `SpriteRegion` はテクスチャ
+/// ポインタが同一になるため、`draw_world` は従来どおりテクスチャ単位でバッチ化できる。
+#[derive(Clone)]
+pub struct SpriteRegion {
+    pub texture: Rc<TextureHandle>,
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+}
+
+impl SpriteRegion {
+    /// テクスチャ全体（UV [0,1]）を指す SpriteRegion を作る。アトラスを使わない、
+    /// これまで通りの「1テクスチャ1スプライト」の見た目はこれで表せる。
+    pub fn full(texture: Rc<TextureHandle>) -> Self {
+        Self { texture, uv_min: [0.0, 0.0], uv_max: [1.0, 1.0] }
+    }
+}
+
+/// `AssetManager::load_model` が読み込んだメッシュ。頂点は position(3) + normal(3) + uv(2)
+/// の32バイト区切りでインターリーブ済みで、`draw_model` はこれをそのまま頂点バッファとして
+/// バインドする。`.obj` が複数サブメッシュを持つ場合もインデックスをオフセットして1本の
+/// 頂点・インデックスバッファへ結合しているので、描画は常に1回の indexed draw で済む。
+pub struct MeshHandle {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    /// 最初に見つかったマテリアルの拡散テクスチャ。`.mtl` がない、もしくは
+    /// `diffuse_texture` を指定していないモデルでは `None` になる。
+    pub diffuse_texture: Option<Rc<TextureHandle>>,
+}
+
+/// `Renderer::create_mesh` で組み立てる、任意形状の2Dメッシュ。頂点は position(2) + uv(2) +
+/// RGB の頂点色(3) の28バイト区切りで、矩形に限らず好きな頂点数・インデックス列を渡せる
+/// （例えば5頂点9インデックスの多角形）。頂点色はラスタライズで補間されてからテクスチャ色へ
+/// 乗算される（単色の矩形なら `[1.0, 1.0, 1.0]` で無変化）。`draw_mesh` で1回の indexed draw
+/// として描画する。
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+
+/// グリフアトラス内にラスタライズ済みの1グリフを指す情報。`(char, px_size)` をキーに
+/// `GlyphAtlas::glyphs` へキャッシュする。`uv_min`/`uv_max` はアトラス全体に対する正規化座標。
+#[derive(Clone, Copy, Debug)]
+struct GlyphInfo {
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    /// ビットマップの幅・高さ（ピクセル単位）。空白文字など描く範囲がない場合は 0。
+    width: f32,
+    height: f32,
+    /// ペン位置（ベースライン上の基準点）からビットマップ左下までのオフセット。
+    xmin: f32,
+    ymin: f32,
+    /// 次の文字のペン位置まで進める量。
+    advance: f32,
+}
+
+/// グリフのラスタライズ結果をキャッシュする、成長可能な単チャンネル（R8）アトラステクスチャ。
+/// シェルフ（棚）方式の単純なパッキングで左から右へ敷き詰め、行が埋まったら次の行へ進む。
+/// 空きが尽きたら `grow` でテクスチャを2倍のサイズに作り直す。既存のグリフのビットマップは
+/// 保持していないため、育てた後はキャッシュを空にし、次に要求されたグリフから順に
+/// 再ラスタライズ・再アップロードさせる。
+struct GlyphAtlas {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+    size: u32,
+    cursor_x: u32,
+    cursor_y: u32,
+    row_height: u32,
+    glyphs: HashMap<(char, u32), GlyphInfo>,
+}
+
+fn create_glyph_atlas_texture(device: &wgpu::Device, size: u32) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Glyph Atlas"),
+        size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+impl GlyphAtlas {
+    fn new(device: &wgpu::Device) -> Self {
+        let (texture, view) = create_glyph_atlas_texture(device, GLYPH_ATLAS_INITIAL_SIZE);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Self {
+            texture,
+            view,
+            sampler,
+            size: GLYPH_ATLAS_INITIAL_SIZE,
+            cursor_x: 0,
+            cursor_y: 0,
+            row_height: 0,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    /// `w` x `h` のグリフ分の空きを探す。現在の行に入らなければ次の行へ、
+    /// テクスチャ自体に入らなければ `None` を返す（呼び出し側で `grow` してから再試行する）。
+    fn try_alloc(&mut self, w: u32, h: u32) -> Option<(u32, u32)> {
+        if self.cursor_x + w > self.size {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_height;
+            self.row_height = 0;
+        }
+        if self.cursor_y + h > self.size {
+            return None;
+        }
+        let pos = (self.cursor_x, self.cursor_y);
+        self.cursor_x += w;
+        self.row_height = self.row_height.max(h);
+        Some(pos)
+    }
+
+    /// アトラスを2倍のサイズに作り直し、パッキング状態とグリフキャッシュをリセットする。
+    fn grow(&mut self, device: &wgpu::Device) {
+        self.size *= 2;
+        let (texture, view) = create_glyph_atlas_texture(device, self.size);
+        self.texture = texture;
+        self.view = view;
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.row_height = 0;
+        self.glyphs.clear();
+        log::debug!(target: "rendering", "GlyphAtlas grown to {}x{}", self.size, self.size);
+    }
+}
+
+/// バッチの合成方法。Ruffle の `ComplexBlend` 列挙を参考にした最小限のセット。
+/// `instanced_pipeline` 1本の固定ブレンドではグローや乗算シャドウが表現できないため、
+/// モードごとに `wgpu::BlendState` だけを変えたパイプラインを用意し、描画時に切り替える。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// 通常の（straight）アルファブレンディング。既定値。
+    Normal,
+    /// 加算合成。グロー・発光・パーティクルのハイライトなど。
+    Add,
+    /// 乗算合成。影落としや暗めのオーバーレイなど。
+    Multiply,
+    /// スクリーン合成。光源の重ね合わせなど、明るくかつ白で飽和させたい場合に使う。
+    Screen,
+}
+
+impl Default for BlendMode {
+    fn default() -> Self {
+        BlendMode::Normal
+    }
+}
+
+impl BlendMode {
+    /// 全バリアント。パイプラインキャッシュの初期化で使う。
+    const ALL: [BlendMode; 4] = [BlendMode::Normal, BlendMode::Add, BlendMode::Multiply, BlendMode::Screen];
+
+    fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            BlendMode::Normal => wgpu::BlendState::ALPHA_BLENDING,
+            BlendMode::Add => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            BlendMode::Multiply => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Zero,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+            BlendMode::Screen => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrc,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrc,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+        }
+    }
+}
+
+/// 指定したサイズ・サンプル数で深度テクスチャとそのビューを作成する。
+fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32, sample_count: u32) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Texture"),
+        size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// インスタンシング描画用の1インスタンス分のデータ（x, y, w, h, z, color_multiply, color_add,
+/// uv_min, uv_max）を詰める。`uv_min`/`uv_max` は `SpriteRegion` の正規化座標で、頂点シェーダーは
+/// ユニットクアッドの UV（常に [0,1]）をこの範囲へ `mix` してアトラス内のフレームを切り出す。
+fn pack_instance(transform: &crate::ecs::Transform, color_multiply: [f32; 4], color_add: [f32; 4], uv_min: [f32; 2], uv_max: [f32; 2]) -> [f32; 17] {
+    [
+        transform.x, transform.y, transform.w, transform.h, transform.z,
+        color_multiply[0], color_multiply[1], color_multiply[2], color_multiply[3],
+        color_add[0], color_add[1], color_add[2], color_add[3],
+        uv_min[0], uv_min[1], uv_max[0], uv_max[1],
+    ]
+}
+
+/// インスタンシング描画パイプラインを、指定した `BlendMode` の色合成状態で1つ作る。
+/// 頂点レイアウト・深度設定・MSAA サンプル数は全バリアントで共有し、変わるのは
+/// `ColorTargetState::blend` のみ。
+fn create_instanced_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    surface_format: wgpu::TextureFormat,
+    blend_mode: BlendMode,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Instanced Sprite Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main_instanced",
+            buffers: &[
+                wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x2,
+                        },
+                    ],
+                },
+                wgpu::VertexBufferLayout {
+                    // x, y, w, h, z, color_multiply(rgba), color_add(rgba), uv_min, uv_max = 17 floats
+                    array_stride: std::mem::size_of::<[f32; 17]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 2,
+                            format: wgpu::VertexFormat::Float32x2, // instance position (x, y)
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                            shader_location: 3,
+                            format: wgpu::VertexFormat::Float32x2, // instance size (w, h)
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                            shader_location: 4,
+                            format: wgpu::VertexFormat::Float32, // instance z
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                            shader_location: 5,
+                            format: wgpu::VertexFormat::Float32x4, // color_multiply (rgba)
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                            shader_location: 6,
+                            format: wgpu::VertexFormat::Float32x4, // color_add (rgba)
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 13]>() as wgpu::BufferAddress,
+                            shader_location: 7,
+                            format: wgpu::VertexFormat::Float32x2, // region uv_min
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 15]>() as wgpu::BufferAddress,
+                            shader_location: 8,
+                            format: wgpu::VertexFormat::Float32x2, // region uv_max
+                        },
+                    ],
+                },
+            ],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(blend_mode.blend_state()),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: SAMPLE_COUNT,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+/// `draw_model` 用のシェーダー。`MeshHandle` の position(3)+normal(3)+uv(2) をそのまま
+/// 頂点属性として受け取る。ライティングは行わず、法線は未使用のまま通しているだけ
+/// （将来 diffuse shading を足すときに頂点レイアウトを変えずに済むように残してある）。
+/// `view_proj` は他のパイプラインと同じ `uniform_bind_group`（group 0）の camera 由来。
+const MODEL_SHADER_SRC: &str = r#"
+struct Uniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0) var<uniform> u: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u.view_proj * vec4<f32>(input.position, 1.0);
+    out.uv = input.uv;
+    return out;
+}
+
+@group(1) @binding(0) var t_diffuse: texture_2d<f32>;
+@group(1) @binding(1) var s_diffuse: sampler;
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, input.uv);
+}
+"#;
+
+/// テキスト描画用のシェーダー。頂点ごとに論理座標・グリフアトラス UV・RGBA 色を受け取り、
+/// フラグメントシェーダーでアトラス（R8、カバレッジのみ）をサンプルして色に乗算する。
+/// NDC への変換は `texture_pipeline` と同じ `uniform_bind_group`（group 0）の view_proj を使う。
+const TEXT_SHADER_SRC: &str = r#"
+struct Uniform {
+    view_proj: mat4x4<f32>,
+};
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> u: Uniform;
+@group(1) @binding(0) var atlas_texture: texture_2d<f32>;
+@group(1) @binding(1) var atlas_sampler: sampler;
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u.view_proj * vec4<f32>(input.position, 0.0, 1.0);
+    out.uv = input.uv;
+    out.color = input.color;
+    return out;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(atlas_texture, atlas_sampler, input.uv).r;
+    return vec4<f32>(input.color.rgb, input.color.a * coverage);
+}
+"#;
+
+/// `Mesh`（`create_mesh`/`draw_mesh`）用のシェーダー。頂点ごとに position・uv に加えて
+/// RGB の頂点色を受け取り、ラスタライズで線形補間してからテクスチャ色へ乗算する。
+/// グラデーションで塗った多角形など、矩形1枚では表現できない見た目に使う
+/// （インスタンスごとの色変換が欲しいだけなら `Sprite::color_multiply`/`color_add` で足りる）。
+const MESH_COLOR_SHADER_SRC: &str = r#"
+struct Uniform {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0) var<uniform> u: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u.view_proj * vec4<f32>(input.position, 0.0, 1.0);
+    out.uv = input.uv;
+    out.color = input.color;
+    return out;
+}
+
+@group(1) @binding(0) var t_mesh: texture_2d<f32>;
+@group(1) @binding(1) var s_mesh: sampler;
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let tex = textureSample(t_mesh, s_mesh, input.uv);
+    return vec4<f32>(tex.rgb * input.color, tex.a);
+}
+"#;
+
+/// `Mesh` 用のパイプラインを作る。`texture_pipeline` と同じ `pipeline_layout`
+/// （group 0: uniform, group 1: texture+sampler）を共有できるが、頂点レイアウトに
+/// RGB の頂点色（shader_location 2）が増える分だけ専用のパイプラインにしてある。
+fn create_mesh_color_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    surface_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Mesh Color Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[wgpu::VertexBufferLayout {
+                // x, y, u, v, r, g, b = 7 floats
+                array_stride: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &[
+                    wgpu::VertexAttribute {
+                        offset: 0,
+                        shader_location: 0,
+                        format: wgpu::VertexFormat::Float32x2, // position
+                    },
+                    wgpu::VertexAttribute {
+                        offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                        shader_location: 1,
+                        format: wgpu::VertexFormat::Float32x2, // uv
+                    },
+                    wgpu::VertexAttribute {
+                        offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                        shader_location: 2,
+                        format: wgpu::VertexFormat::Float32x3, // color
+                    },
+                ],
+            }],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+/// テキスト描画パイプラインを作る。`texture_pipeline` / `instanced_pipelines` と同じ
+/// `pipeline_layout`（group 0: uniform, group 1: texture+sampler）を共有できる
+/// （グリフアトラスも texture + sampler の組なので bind group layout が一致する）。
+/// グリフのカバレッジをアルファとして使うため、`REPLACE` ではなく
+/// `BlendState::ALPHA_BLENDING` を使う。
+///
+/// `sample_count` は呼び出し先のレンダーパスに合わせる。`draw_text` は `texture_pipeline` と
+/// 同様に単独の非 MSAA パスを開くので 1、`draw_world` は既存の MSAA パスにそのまま積むので
+/// `SAMPLE_COUNT` を渡す。
+fn create_text_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    surface_format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Text Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[wgpu::VertexBufferLayout {
+                // x, y, u, v, r, g, b, a = 8 floats
+                array_stride: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &[
+                    wgpu::VertexAttribute {
+                        offset: 0,
+                        shader_location: 0,
+                        format: wgpu::VertexFormat::Float32x2, // position
+                    },
+                    wgpu::VertexAttribute {
+                        offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                        shader_location: 1,
+                        format: wgpu::VertexFormat::Float32x2, // uv
+                    },
+                    wgpu::VertexAttribute {
+                        offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                        shader_location: 2,
+                        format: wgpu::VertexFormat::Float32x4, // color
+                    },
+                ],
+            }],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+/// 指定したサイズで MSAA カラーバッファ（解決前のマルチサンプルテクスチャ）を作成する。
+fn create_msaa_framebuffer(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("MSAA Framebuffer"),
+        size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: SAMPLE_COUNT,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// ポストプロセスチェインの各パスに共通のフルスクリーン頂点シェーダー。頂点バッファを使わず
+/// `@builtin(vertex_index)` だけから画面全体を覆う1枚の三角形を生成する定番のテクニック。
+const POST_PROCESS_VERTEX_SHADER_SRC: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let x = f32((vertex_index << 1u) & 2u);
+    let y = f32(vertex_index & 2u);
+    out.uv = vec2<f32>(x, y);
+    out.position = vec4<f32>(x * 2.0 - 1.0, 1.0 - y * 2.0, 0.0, 1.0);
+    return out;
+}
+"#;
+
+/// プリセットファイルが指定されなかった場合に使う既定のパス。前段の出力をそのまま通すだけ。
+const POST_PROCESS_PASSTHROUGH_FRAGMENT_SHADER_SRC: &str = r#"
+struct PostProcessUniform {
+    output_resolution: vec2<f32>,
+    source_resolution: vec2<f32>,
+    frame: f32,
+    time: f32,
+};
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var<uniform> pp: PostProcessUniform;
+
+@fragment
+fn fs_main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(source_texture, source_sampler, uv);
+}
+"#;
+
+/// 指定したサイズでポストプロセス用のオフスクリーンターゲット（色のみ、非マルチサンプル）を作成する。
+///
+/// `format` は呼び出し側から常に `surface_format` を渡す。サーフェスが sRGB フォーマットの
+/// 場合はオフスクリーンも同じ sRGB フォーマットになるため、`textureSample` での読み出しと
+/// 書き込みの両方で GPU が自動的にエンコード/デコードを行い、色がズレることはない。
+fn create_post_process_target(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Post Process Target"),
+        size: wgpu::Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// ポストプロセスの1パス分の `wgpu::RenderPipeline` を作る。頂点シェーダーは全パス共通
+/// （フルスクリーン三角形）で、フラグメントシェーダーのみパスごとに異なる。
+fn create_post_process_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    vertex_shader: &wgpu::ShaderModule,
+    fragment_shader: &wgpu::ShaderModule,
+    target_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Post Process Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: vertex_shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: fragment_shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+/// ポストプロセスの uniform ブロック（output_resolution, source_resolution, frame, time）を
+/// 16バイト境界に合わせて詰める。
+fn pack_post_process_uniform(output_resolution: [f32; 2], source_resolution: [f32; 2], frame: f32, time: f32) -> [f32; 8] {
+    [
+        output_resolution[0], output_resolution[1],
+        source_resolution[0], source_resolution[1],
+        frame, time,
+        0.0, 0.0,
+    ]
+}
+
+/// ポストプロセスのプリセットファイルをパースし、パスの並び（シェーダーパスのリスト）を返す。
+///
+/// フォーマットは `key = value` の行の並びで、`#` から始まる行とコメントは無視する:
+/// ```text
+/// passes = 2
+/// shader1 = assets/shaders/scanline.wgsl
+/// shader2 = assets/shaders/vignette.wgsl
+/// ```
+fn parse_post_process_preset(path: &str) -> Vec<PassDesc> {
+    let text = std::fs::read_to_string(path).expect("Failed to read post-process preset file");
+    let mut entries: HashMap<String, String> = HashMap::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once('=').expect("Invalid preset line (expected `key = value`)");
+        entries.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    let pass_count: usize = entries
+        .get("passes")
+        .expect("Preset file missing `passes` entry")
+        .parse()
+        .expect("`passes` must be an integer");
+    (1..=pass_count)
+        .map(|i| {
+            let key = format!("shader{}", i);
+            let shader_path = entries
+                .get(&key)
+                .unwrap_or_else(|| panic!("Preset file missing `{}` entry", key))
+                .clone();
+            // `scaleN` は省略可能（既定 1.0）。出力テクスチャをロジカル解像度の何倍で確保するか
+            // を指定する（例: ブルームの下り道で 0.5 を指定して先にダウンサンプルするなど）。
+            let scale: f32 = entries
+                .get(&format!("scale{}", i))
+                .map(|v| v.parse().unwrap_or_else(|_| panic!("`scale{}` must be a number", i)))
+                .unwrap_or(1.0);
+            PassDesc { shader_path, scale }
+        })
+        .collect()
+}
+
+impl Renderer {
+    /// Renderer構造体の初期化。
+    /// ウィンドウと連携し、WGPUの初期化・パイプライン・バインドレイアウトをセットアップする。
+    pub async fn new(window: &Window) -> Self {
+        use wgpu::util::DeviceExt;
+
+        // ウィンドウサイズ取得（物理サイズ）
+        let size = window.inner_size();
+
+        // 固定の論理サイズ
+        let logical_width: f32 = 800.0;
+        let logical_height: f32 = 600.0;
+
+        // WGPUインスタンスとサーフェス作成。
+        // wasm32 ではブラウザが WebGL2 経由でしか wgpu を動かせないことが多く、`Backends::all()`
+        // で他のバックエンド（Vulkan 等）を試みるとサーフェス生成に失敗するので GL に絞る。
+        let backends = if cfg!(target_arch = "wasm32") { wgpu::Backends::GL } else { wgpu::Backends::all() };
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        let surface = unsafe { instance.create_surface(window) }.unwrap();
+
+        // アダプター取得
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..Default::default()
+        }).await.unwrap();
+
+        // デバイスとキューの作成。
+        // WebGL2 はネイティブの `Limits::default()` を満たせないので、wasm32 では
+        // `downlevel_webgl2_defaults()` まで要求を緩める。
+        let limits = if cfg!(target_arch = "wasm32") {
+            wgpu::Limits::downlevel_webgl2_defaults()
+        } else {
+            wgpu::Limits::default()
+        };
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor { label: None, features: wgpu::Features::empty(), limits },
+                None,
+            )
+            .await
+            .unwrap();
+
+        // サーフェスのフォーマットと設定
+        let surface_format = surface.get_capabilities(&adapter).formats[0];
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![surface_format],
+        };
+        surface.configure(&device, &config);
+
+        // バインドグループレイアウト（group 0: uniforms）
+        let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Uniform BindGroup Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        // 固定の論理サイズを基準にカメラを作り、view_proj 行列を初期値として uniform に積む
+        // (既定の position/zoom/rotation では、論理座標 (0,0)-(800,600) を NDC (-1,-1)-(1,1) に
+        // 変換するだけの、カメラ導入以前と同じ変換になる)
+        let camera = Camera2D::new(logical_width, logical_height);
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&camera.view_proj()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Uniform BindGroup"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        // 次に、group 1: texture + sampler のレイアウトを作成
+        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture BindGroup Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        // シェーダー読み込み
+        // `vs_main` / `vs_main_instanced` はどちらも group 0 binding 0 の Uniform を
+        // `view_proj: mat4x4<f32>` として読み、`view_proj * vec4<f32>(position, 0.0, 1.0)` で
+        // クリップ座標を計算する想定（旧 `scale: vec2<f32>` 版からの移行は asset 側で対応すること）。
+        // `vs_main_instanced` は location 7/8 でインスタンスごとの `uv_min`/`uv_max` を受け取り、
+        // ユニットクアッドの UV（location 1、常に [0,1]）をこの範囲へ `mix` してからサンプリングに
+        // 使うこと（`SpriteRegion` によるテクスチャアトラスのフレーム切り出し。asset 側対応が必要）。
+        let shader_src = std::fs::read_to_string("assets/shader_texture.wgsl").unwrap();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Texture Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_src.into()),
+        });
+
+        // パイプラインレイアウト（2つのbind group layoutを指定）
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Texture Pipeline Layout"),
+            bind_group_layouts: &[
+                &uniform_bind_group_layout,
+                &texture_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        // テクスチャ描画用のパイプライン作成
+        let texture_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Texture Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x2,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x2,
+                        },
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // `draw_model` 用パイプライン。bind group layout は texture_pipeline と同じ
+        // pipeline_layout（group 0: uniform, group 1: texture+sampler）を使い回す。
+        let model_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Model Shader"),
+            source: wgpu::ShaderSource::Wgsl(MODEL_SHADER_SRC.into()),
+        });
+        let model_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Model Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &model_shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &[
+                        wgpu::VertexAttribute {
+                            offset: 0,
+                            shader_location: 0,
+                            format: wgpu::VertexFormat::Float32x3,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                            shader_location: 1,
+                            format: wgpu::VertexFormat::Float32x3,
+                        },
+                        wgpu::VertexAttribute {
+                            offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                            shader_location: 2,
+                            format: wgpu::VertexFormat::Float32x2,
+                        },
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &model_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        // `draw_mesh` 用の、頂点ごとの RGB 頂点色つきパイプライン。bind group layout は
+        // texture_pipeline と同じ pipeline_layout を使い回す。
+        let mesh_color_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Color Shader"),
+            source: wgpu::ShaderSource::Wgsl(MESH_COLOR_SHADER_SRC.into()),
+        });
+        let mesh_color_pipeline = create_mesh_color_pipeline(&device, &pipeline_layout, &mesh_color_shader, surface_format);
+
+        // インスタンシング描画用パイプライン。BlendMode ごとに色合成だけが異なるバリアントを
+        // 用意する（buffer 0: ユニットクアッドの頂点（ローカル座標 + UV）、step_mode: Vertex、
+        // buffer 1: インスタンスごとのデータ（x, y, w, h, z, color_multiply, color_add, uv_min, uv_max）、
+        // step_mode: Instance。頂点シェーダー側で `instance.pos + unit_quad * instance.size`
+        // を計算する想定）。
+        let instanced_pipelines: HashMap<BlendMode, wgpu::RenderPipeline> = BlendMode::ALL
+            .iter()
+            .map(|&blend_mode| {
+                (blend_mode, create_instanced_pipeline(&device, &pipeline_layout, &shader, surface_format, blend_mode))
+            })
+            .collect();
+
+        // テキスト描画用パイプライン。bind group layout は texture_pipeline / instanced_pipelines
+        // と共有し（group 0: uniform, group 1: texture+sampler）、新しいのはシェーダーと
+        // ブレンド設定（カバレッジをアルファに使うので ALPHA_BLENDING）だけ。
+        let text_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Text Shader"),
+            source: wgpu::ShaderSource::Wgsl(TEXT_SHADER_SRC.into()),
+        });
+        // 非 MSAA 版（draw_text が単独で開くパス用）と MSAA 版（draw_world の既存パスに積む用）を両方作る。
+        let text_pipeline = create_text_pipeline(&device, &pipeline_layout, &text_shader, surface_format, 1);
+        let text_pipeline_msaa =
+            create_text_pipeline(&device, &pipeline_layout, &text_shader, surface_format, SAMPLE_COUNT);
+
+        let font_data = std::fs::read(DEFAULT_FONT_PATH).expect("Failed to read font file");
+        let font = Font::from_bytes(font_data, FontSettings::default()).expect("Failed to parse font");
+        let glyph_atlas = GlyphAtlas::new(&device);
+
+        // テキストの頂点・インデックスバッファ（draw_text / draw_world 共通）。インスタンシングでは
+        // なく draw_texture と同じ頂点+インデックス方式で、1文字につき頂点4つ・インデックス6つを積む。
+        // バッファが足りなくなったら ensure_text_*_buffer_capacity で拡張する。
+        let text_vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Vertex Buffer"),
+            size: 4096,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let text_index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Index Buffer"),
+            size: 4096,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // インデックスバッファ（四角形）
+        let index_data: [u16; 6] = [0, 1, 2, 2, 3, 0];
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(&index_data),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // ユニットクアッド（(0,0)-(1,1)）。インスタンシング描画では、この頂点をインスタンスごとの
+        // position/size でスケール・平行移動してワールド座標を得る。
+        let unit_quad_vertex_data: [[f32; 4]; 4] = [
+            [0.0, 1.0, 0.0, 0.0], // 左上
+            [1.0, 1.0, 1.0, 0.0], // 右上
+            [1.0, 0.0, 1.0, 1.0], // 右下
+            [0.0, 0.0, 0.0, 1.0], // 左下
+        ];
+        let unit_quad_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Unit Quad Vertex Buffer"),
+            contents: bytemuck::cast_slice(&unit_quad_vertex_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        // ダミー頂点バッファ（必要に応じて draw 時に書き換える）
+        let vertex_data: [[f32; 4]; 4] = [[0.0; 4]; 4];
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertex_data),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // 新たにダブルバッファを初期化（サイズは例として 4096 バイト）
+        let batched_vertex_buffer_0 = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Batched Vertex Buffer 0"),
+            size: 4096,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let batched_vertex_buffer_1 = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Batched Vertex Buffer 1"),
+            size: 4096,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let batched_index_buffer_0 = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Batched Index Buffer 0"),
+            size: 4096,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let batched_index_buffer_1 = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Batched Index Buffer 1"),
+            size: 4096,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // AssetManager のキャッシュやその他のフィールドも初期化
+        let texture_bind_group_cache = std::cell::RefCell::new(HashMap::new());
+
+        let batched_index_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Batched Index Buffer"),
+            size: 32 * 1024, // 32KB
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let (depth_texture, depth_view) = create_depth_texture(&device, config.width, config.height, 1);
+        // MSAA フレームバッファ・深度バッファは post_process_targets と同じ論理解像度で作る。
+        // draw_world / draw_sprites_batched はここへ描画したあとポストプロセスチェインへ回すので、
+        // resolve_target（post_process_targets[0]）とサイズが一致していないと resolve に失敗する。
+        let (msaa_framebuffer, msaa_framebuffer_view) =
+            create_msaa_framebuffer(&device, surface_format, logical_width as u32, logical_height as u32);
+        let (msaa_depth_texture, msaa_depth_view) =
+            create_depth_texture(&device, logical_width as u32, logical_height as u32, SAMPLE_COUNT);
+
+        // ポストプロセスチェインへの "source"。world は常にこの固定の論理解像度
+        // （logical_width × logical_height）へ描画する。ウィンドウの物理サイズとは
+        // 独立しているので、最終パスでスワップチェインへブリットするまで解像度非依存になる。
+        let (post_process_target_0, post_process_target_view_0) =
+            create_post_process_target(&device, surface_format, logical_width as u32, logical_height as u32);
+
+        let post_process_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let post_process_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process BindGroupLayout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let post_process_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Process PipelineLayout"),
+            bind_group_layouts: &[&post_process_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let post_process_vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Process Vertex Shader"),
+            source: wgpu::ShaderSource::Wgsl(POST_PROCESS_VERTEX_SHADER_SRC.into()),
+        });
+        let post_process_passthrough_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Process Passthrough Fragment Shader"),
+            source: wgpu::ShaderSource::Wgsl(POST_PROCESS_PASSTHROUGH_FRAGMENT_SHADER_SRC.into()),
+        });
+        let post_process_passthrough_pipeline = create_post_process_pipeline(
+            &device,
+            &post_process_pipeline_layout,
+            &post_process_vertex_shader,
+            &post_process_passthrough_shader,
+            surface_format,
+        );
+        let post_process_passes = vec![PostProcessPass {
+            shader_path: "<built-in passthrough>".to_string(),
+            pipeline: post_process_passthrough_pipeline,
+            uniform_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Post Process Uniform Buffer"),
+                size: (std::mem::size_of::<[f32; 8]>()) as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            scale: 1.0,
+            // 唯一のパスが常に最後のパスなので、出力先はサーフェス（self-referential な
+            // 中間テクスチャは不要）。
+            output: None,
+        }];
+
+        // 構造体の生成・返却
+        Self {
+            device,
+            queue,
+            surface,
+            config,
+            surface_format,
+            texture_pipeline,
+            model_pipeline,
+            mesh_color_pipeline,
+            instanced_pipelines,
+            texture_bind_group_layout,
+            vertex_buffer,
+            unit_quad_vertex_buffer,
+            index_buffer,
+            uniform_buffer,
+            uniform_bind_group,
+            camera,
+            texture_bind_group_cache,
+
+            batched_vertex_buffers: [batched_vertex_buffer_0, batched_vertex_buffer_1],
+            batched_index_buffers: [batched_index_buffer_0, batched_index_buffer_1],
+            current_buffer: 0,
+            batched_vertex_buffer_capacity: [4096, 4096],
+
+            batched_index_buffer,
+            sprite_bundle_cache: HashMap::new(),
+
+            depth_texture,
+            depth_view,
+
+            msaa_framebuffer,
+            msaa_framebuffer_view,
+            msaa_depth_texture,
+            msaa_depth_view,
+
+            post_process_targets: [post_process_target_0],
+            post_process_target_views: [post_process_target_view_0],
+            post_process_sampler,
+            post_process_vertex_shader,
+            post_process_bind_group_layout,
+            post_process_pipeline_layout,
+            post_process_passes,
+            frame_counter: 0,
+            start_time: Instant::now(),
+
+            letterbox_viewport: compute_letterbox_viewport(
+                config.width,
+                config.height,
+                logical_width as u32,
+                logical_height as u32,
+                false,
+            ),
+
+            font,
+            glyph_atlas,
+            text_pipeline,
+            text_pipeline_msaa,
+            text_vertex_buffer,
+            text_index_buffer,
+            text_vertex_buffer_capacity: 4096,
+            text_index_buffer_capacity: 4096,
+            pending_texts: Vec::new(),
+        }
+    }
+
+    /// camera の現在値から view_proj を計算し、uniform_buffer へ書き込む。
+    fn upload_camera(&self) {
+        self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&self.camera.view_proj()));
+    }
+
+    /// カメラの基準ビューポート（論理解像度）を
+    /// 設定し直し、即座に uniform へ反映する。`resize` が内部でも呼ぶほか、`App::new` の
+    /// 初期化時にも使う。
+    pub fn set_camera_viewport(&mut self, viewport_width: f32, viewport_height: f32) {
+        self.camera.set_viewport(viewport_width, viewport_height);
+        self.upload_camera();
+    }
+
+    /// カメラの位置（ビューポート左下隅に写るワールド座標）を設定し、即座に uniform へ反映する。
+    pub fn set_camera_position(&mut self, x: f32, y: f32) {
+        self.camera.set_position(x, y);
+        self.upload_camera();
+    }
+
+    /// カメラのズーム値に `factor` を掛け、即座に uniform へ反映する。
+    pub fn zoom_camera_by(&mut self, factor: f32) {
+        self.camera.zoom_by(factor);
+        self.upload_camera();
+    }
+
+    /// ウィンドウサイズが変更されたときの処理。
+    /// 新しい物理サイズでサーフェスを再構成し、stretch_mode に応じてレターボックス矩形を作り直す。
+    /// uniform_buffer のカメラは stretch_mode に関わらず論理解像度を基準に更新する。
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>, config: &GameConfig) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            self.surface.configure(&self.device, &self.config);
+
+            // サーフェスと同じサイズに（非 MSAA の）単サンプル深度バッファを作り直す。
+            // これは draw_texture / draw_model がスワップチェインへ直接書くときに使う。
+            let (depth_texture, depth_view) = create_depth_texture(&self.device, new_size.width, new_size.height, 1);
+            self.depth_texture = depth_texture;
+            self.depth_view = depth_view;
+
+            // MSAA フレームバッファ・post_process_targets は GameConfig の論理解像度で作り直す
+            // （draw_world / draw_sprites_batched は常にこの解像度へ描画し、最終パスだけが
+            // スワップチェインへ直接書くので、サーフェスのサイズには合わせない）。
+            // サイズは論理解像度だけで決まりウィンドウの物理サイズには依存しないので、
+            // 論理解像度が前回と同じならテクスチャはそのまま使い回し、無駄な再作成を避ける。
+            let current_logical_size = self.post_process_targets[0].size();
+            if current_logical_size.width != config.logical_width || current_logical_size.height != config.logical_height {
+                let (msaa_framebuffer, msaa_framebuffer_view) =
+                    create_msaa_framebuffer(&self.device, self.surface_format, config.logical_width, config.logical_height);
+                self.msaa_framebuffer = msaa_framebuffer;
+                self.msaa_framebuffer_view = msaa_framebuffer_view;
+
+                let (msaa_depth_texture, msaa_depth_view) =
+                    create_depth_texture(&self.device, config.logical_width, config.logical_height, SAMPLE_COUNT);
+                self.msaa_depth_texture = msaa_depth_texture;
+                self.msaa_depth_view = msaa_depth_view;
+
+                let (post_process_target_0, post_process_target_view_0) =
+                    create_post_process_target(&self.device, self.surface_format, config.logical_width, config.logical_height);
+                self.post_process_targets = [post_process_target_0];
+                self.post_process_target_views = [post_process_target_view_0];
+
+                // 各パスの出力テクスチャ（最終パス以外）も `scale` に応じたサイズで作り直す。
+                for pass in self.post_process_passes.iter_mut() {
+                    if pass.output.is_some() {
+                        let width = ((config.logical_width as f32) * pass.scale).max(1.0) as u32;
+                        let height = ((config.logical_height as f32) * pass.scale).max(1.0) as u32;
+                        pass.output = Some(create_post_process_target(&self.device, self.surface_format, width, height));
+                    }
+                }
+            }
+
+            // 最終ブリットパスのレターボックス矩形も、新しいサーフェスサイズと GameConfig の
+            // 論理解像度・stretch_mode から作り直す。
+            self.letterbox_viewport = compute_letterbox_viewport(
+                new_size.width,
+                new_size.height,
+                config.logical_width,
+                config.logical_height,
+                config.stretch_mode,
+            );
+
+            // world は常に論理解像度のオフスクリーンターゲットへ描画するので、カメラの
+            // 基準ビューポートは stretch_mode に関わらず論理解像度で固定する。ウィンドウへの
+            // 引き伸ばし／レターボックスは最終ブリットパスの viewport（letterbox_viewport）
+            // だけが担当する。
+            self.set_camera_viewport(config.logical_width as f32, config.logical_height as f32);
+        }
+    }
+
+    /// テクスチャを読み込み、GPUへ転送して TextureHandle を返す。
+    /// 
+    /// # 引数
+    /// * `path` - 画像ファイルのパス
+    ///
+    /// # 戻り値
+    /// * `TextureHandle` - view + sampler を含む構造体
+    pub fn load_texture(&self, path: &str) -> TextureHandle {
+        use image::GenericImageView;
+    
+        let img = image::open(path).expect("Failed to open image").to_rgba8();
+        let (width, height) = img.dimensions();
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+    
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("User Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+    
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &img,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+    
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+    
+        TextureHandle {
+            texture, // テクスチャ本体を保持する
+            view,
+            sampler,
+        }
+    }
+    
+
+    /// 指定したテクスチャを、指定した領域に描画する。
+    ///
+    /// # 引数
+    /// * `encoder` - コマンドエンコーダ
+    /// * `view` - 描画対象のテクスチャビュー
+    /// * `texture` - 描画対象のテクスチャ（ハンドル）
+    /// * `x`, `y`, `w`, `h` - 描画する矩形の左下座標とサイズ（論理座標）
+    pub fn draw_texture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        texture: &TextureHandle,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+    ) {
+        // draw_world を経由しない単独呼び出しの可能性があるので、ここでも view_proj を書き込む
+        self.upload_camera();
+
+        // ここでは論理座標系（0,0)-(800,600) を前提とするので、
+        // 頂点データはそのまま論理座標で渡す
+        let vertex_data = [
+            [x, y + h, 0.0, 0.0],     // 左上
+            [x + w, y + h, 1.0, 0.0],   // 右上
+            [x + w, y, 1.0, 1.0],       // 右下
+            [x, y, 0.0, 1.0],           // 左下
+        ];
+    
+        self.queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertex_data));
+    
+        // テクスチャ用 bind group を作成（group 1）
+        let texture_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some("Texture BindGroup"),
+        });
+    
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Texture Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+    
+        // パイプラインを最初にセットする
+        pass.set_pipeline(&self.texture_pipeline);
+    
+        // シェーダーのバインド順に合わせる
+        pass.set_bind_group(0, &self.uniform_bind_group, &[]); // ユニフォーム（group 0）
+        pass.set_bind_group(1, &texture_bind_group, &[]);        // テクスチャ＋サンプラー（group 1）
+    
+        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        pass.draw_indexed(0..6, 0, 0..1);
+    }
+
+    /// `AssetManager::load_model` で読み込んだ `MeshHandle` を1回の indexed draw で描画する。
+    /// `draw_texture` と同様、呼び出し側のパスに相乗りせず自前の RenderPass を開く単独の
+    /// エントリポイント。モデルに拡散テクスチャがない（`.mtl` 未指定など）場合は、
+    /// バインドするテクスチャがないので描画せずログに警告を出すだけで済ませる。
+    ///
+    /// # 引数
+    /// * `encoder` - コマンドエンコーダ
+    /// * `view` - 描画対象のテクスチャビュー
+    /// * `model` - 描画対象のメッシュ（ハンドル）
+    pub fn draw_model(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        model: &MeshHandle,
+    ) {
+        let diffuse_texture = match model.diffuse_texture.as_deref() {
+            Some(texture) => texture,
+            None => {
+                log::warn!(target: "rendering", "draw_model: model has no diffuse texture, skipping draw");
+                return;
+            }
+        };
+
+        // draw_texture / draw_world を経由しない単独呼び出しの可能性があるので、ここでも view_proj を書き込む
+        self.upload_camera();
+
+        let texture_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+                },
+            ],
+            label: Some("Model Texture BindGroup"),
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Model Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        pass.set_pipeline(&self.model_pipeline);
+        pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        pass.set_bind_group(1, &texture_bind_group, &[]);
+        pass.set_vertex_buffer(0, model.vertex_buffer.slice(..));
+        pass.set_index_buffer(model.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        pass.draw_indexed(0..model.index_count, 0, 0..1);
+    }
+
+    /// position(x, y) + uv(u, v) + color(r, g, b) の頂点列と `u16` インデックスから
+    /// 任意形状の `Mesh` を組み立てる。矩形専用の `self.vertex_buffer`/`self.index_buffer`
+    /// （`draw_texture` が毎フレーム書き換える）とは違い、呼び出し側が一度作って使い回す
+    /// 固定のバッファを持つ。
+    pub fn create_mesh(&self, vertices: &[[f32; 7]], indices: &[u16]) -> Mesh {
+        let vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Mesh { vertex_buffer, index_buffer, index_count: indices.len() as u32 }
+    }
+
+    /// 原点中心、一辺1.0の白色ユニットクアッド（position [-0.5, 0.5]、uv [0, 1]）を作る。
+    /// スプライト1枚をそのまま表示したいだけなら `draw_texture` で十分だが、これは
+    /// `draw_mesh` の最小の動作例として、また他形状のメッシュを組み立てる際のひな形として使える。
+    pub fn unit_quad_mesh(&self) -> Mesh {
+        self.create_mesh(
+            &[
+                [-0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 1.0],  // 左上
+                [0.5, 0.5, 1.0, 0.0, 1.0, 1.0, 1.0],   // 右上
+                [0.5, -0.5, 1.0, 1.0, 1.0, 1.0, 1.0],  // 右下
+                [-0.5, -0.5, 0.0, 1.0, 1.0, 1.0, 1.0], // 左下
+            ],
+            &[0, 1, 2, 0, 2, 3],
+        )
+    }
+
+    /// `create_mesh`/`unit_quad_mesh` で組み立てた任意形状の `Mesh` を1回の indexed draw で
+    /// 描画する。`mesh_color_pipeline`（position + uv + 頂点色の頂点レイアウト）を使うので、
+    /// 矩形に限らず5頂点9インデックスの多角形や、頂点ごとに色を変えたグラデーションも
+    /// 同じ仕組みで描ける。
+    pub fn draw_mesh(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        mesh: &Mesh,
+        texture: &TextureHandle,
+    ) {
+        self.upload_camera();
+
+        let texture_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some("Mesh Texture BindGroup"),
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Mesh Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        pass.set_pipeline(&self.mesh_color_pipeline);
+        pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        pass.set_bind_group(1, &texture_bind_group, &[]);
+        pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+    }
+
+    /// `(ch, px_size)` のグリフをキャッシュから探し、なければラスタライズしてアトラスに
+    /// アップロードする。アトラスに空きがなければ `GlyphAtlas::grow` で2倍に育ててから
+    /// 積み直す（育てた直後はキャッシュが空なので、このグリフも含めて必ず入る）。
+    fn ensure_glyph(&mut self, ch: char, px_size: u32) -> GlyphInfo {
+        if let Some(info) = self.glyph_atlas.glyphs.get(&(ch, px_size)) {
+            return *info;
+        }
+
+        let (metrics, bitmap) = self.font.rasterize(ch, px_size as f32);
+
+        // 空白など描く範囲がない文字は、アトラスへのアップロードを省略して advance だけ控える。
+        if metrics.width == 0 || metrics.height == 0 {
+            let info = GlyphInfo {
+                uv_min: [0.0, 0.0],
+                uv_max: [0.0, 0.0],
+                width: 0.0,
+                height: 0.0,
+                xmin: metrics.xmin as f32,
+                ymin: metrics.ymin as f32,
+                advance: metrics.advance_width,
+            };
+            self.glyph_atlas.glyphs.insert((ch, px_size), info);
+            return info;
+        }
+
+        let (w, h) = (metrics.width as u32, metrics.height as u32);
+        let (x, y) = match self.glyph_atlas.try_alloc(w, h) {
+            Some(pos) => pos,
+            None => {
+                self.glyph_atlas.grow(&self.device);
+                self.glyph_atlas
+                    .try_alloc(w, h)
+                    .expect("Glyph atlas still full immediately after growing")
+            }
+        };
+
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.glyph_atlas.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &bitmap,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(w),
+                rows_per_image: Some(h),
+            },
+            wgpu::Extent3d { width: w, height: h, depth_or_array_layers: 1 },
+        );
+
+        let atlas_size = self.glyph_atlas.size as f32;
+        let info = GlyphInfo {
+            uv_min: [x as f32 / atlas_size, y as f32 / atlas_size],
+            uv_max: [(x + w) as f32 / atlas_size, (y + h) as f32 / atlas_size],
+            width: w as f32,
+            height: h as f32,
+            xmin: metrics.xmin as f32,
+            ymin: metrics.ymin as f32,
+            advance: metrics.advance_width,
+        };
+        self.glyph_atlas.glyphs.insert((ch, px_size), info);
+        info
+    }
+
+    /// `text` を `(x, y)` をベースライン位置として頂点・インデックスに変換し、既存の
+    /// `out_vertices` / `out_indices` の末尾に追加する（呼び出し側で複数回呼んで積み重ねられる
+    /// ように、絶対頂点インデックスで書き込む）。
+    fn append_text_mesh(
+        &mut self,
+        out_vertices: &mut Vec<[f32; 8]>,
+        out_indices: &mut Vec<u16>,
+        text: &str,
+        x: f32,
+        y: f32,
+        px_size: f32,
+        color: [f32; 4],
+    ) {
+        let px_size = px_size.round().max(1.0) as u32;
+        let mut pen_x = x;
+
+        for ch in text.chars() {
+            let glyph = self.ensure_glyph(ch, px_size);
+
+            if glyph.width > 0.0 && glyph.height > 0.0 {
+                let left = pen_x + glyph.xmin;
+                let bottom = y + glyph.ymin;
+                let right = left + glyph.width;
+                let top = bottom + glyph.height;
+                let [u0, v0] = glyph.uv_min;
+                let [u1, v1] = glyph.uv_max;
+
+                let base = out_vertices.len() as u16;
+                out_vertices.push([left, top, u0, v0, color[0], color[1], color[2], color[3]]); // 左上
+                out_vertices.push([right, top, u1, v0, color[0], color[1], color[2], color[3]]); // 右上
+                out_vertices.push([right, bottom, u1, v1, color[0], color[1], color[2], color[3]]); // 右下
+                out_vertices.push([left, bottom, u0, v1, color[0], color[1], color[2], color[3]]); // 左下
+                out_indices.extend_from_slice(&[base, base + 1, base + 2, base + 2, base + 3, base]);
+            }
+
+            pen_x += glyph.advance;
+        }
+    }
+
+    /// `self.text_vertex_buffer` / `self.text_index_buffer` が `required_bytes` を下回る場合、
+    /// 同じ用途のバッファを作り直して拡張する。
+    fn ensure_text_vertex_buffer_capacity(&mut self, required_bytes: u64) {
+        if self.text_vertex_buffer_capacity >= required_bytes {
+            return;
+        }
+        let new_capacity = required_bytes.max(self.text_vertex_buffer_capacity * 2);
+        self.text_vertex_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Vertex Buffer (grown)"),
+            size: new_capacity,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.text_vertex_buffer_capacity = new_capacity;
+    }
+
+    fn ensure_text_index_buffer_capacity(&mut self, required_bytes: u64) {
+        if self.text_index_buffer_capacity >= required_bytes {
+            return;
+        }
+        let new_capacity = required_bytes.max(self.text_index_buffer_capacity * 2);
+        self.text_index_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Text Index Buffer (grown)"),
+            size: new_capacity,
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.text_index_buffer_capacity = new_capacity;
+    }
+
+    /// 文字列を1つ、次の `draw_world` で描画されるようキューへ積む。ECS の Text コンポーネント
+    /// を使うほどでもない HUD・スコア表示のような使い捨てのテキストに向いた、即時モードの
+    /// エントリポイント。位置はエンジンの論理座標系（レターボックスの内側）のベースラインで
+    /// 指定し、`world.query_texts()` と同じ扱いで `draw_world` 側のテキストバッチへまとめられる。
+    pub fn queue_text(&mut self, text: &str, x: f32, y: f32, px_size: f32, color: [f32; 4]) {
+        self.pending_texts.push(PendingText { content: text.to_string(), x, y, size: px_size, color });
+    }
+
+    /// 文字列を1つ、指定した論理座標のベースラインへ描画する。`draw_texture` と同じように
+    /// 呼び出しごとに単独のレンダーパスを開き、MSAA を使わず `view` へ直接書き込む
+    /// （直前に同じ `view` へ描画された内容は `LoadOp::Load` で保持したまま重ねる）。
+    pub fn draw_text(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        text: &str,
+        x: f32,
+        y: f32,
+        px_size: f32,
+        color: [f32; 4],
+    ) {
+        let mut vertices: Vec<[f32; 8]> = Vec::new();
+        let mut indices: Vec<u16> = Vec::new();
+        self.append_text_mesh(&mut vertices, &mut indices, text, x, y, px_size, color);
+        if indices.is_empty() {
+            return;
+        }
+
+        // draw_world を経由しない単独呼び出しの可能性があるので、ここでも view_proj を書き込む
+        self.upload_camera();
+
+        self.ensure_text_vertex_buffer_capacity((vertices.len() * std::mem::size_of::<[f32; 8]>()) as u64);
+        self.ensure_text_index_buffer_capacity((indices.len() * std::mem::size_of::<u16>()) as u64);
+        self.queue.write_buffer(&self.text_vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        self.queue.write_buffer(&self.text_index_buffer, 0, bytemuck::cast_slice(&indices));
+
+        let atlas_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Glyph Atlas BindGroup"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.glyph_atlas.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.glyph_atlas.sampler),
+                },
+            ],
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Text Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+        pass.set_pipeline(&self.text_pipeline);
+        pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        pass.set_bind_group(1, &atlas_bind_group, &[]);
+        pass.set_vertex_buffer(0, self.text_vertex_buffer.slice(..));
+        pass.set_index_buffer(self.text_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        pass.draw_indexed(0..indices.len() as u32, 0, 0..1);
+    }
+
+    /// バッファが `required_bytes` を下回る場合、同じ用途のバッファを作り直して拡張する。
+    /// ダブルバッファのどちらのスロットを growしているかは呼び出し側が `slot` で指定する。
+    fn ensure_instance_buffer_capacity(&mut self, slot: usize, required_bytes: u64) {
+        if self.batched_vertex_buffer_capacity[slot] >= required_bytes {
+            return;
+        }
+        // 倍々で拡張し、growのたびにバッファを作り直す頻度を抑える
+        let new_capacity = required_bytes.max(self.batched_vertex_buffer_capacity[slot] * 2);
+        self.batched_vertex_buffers[slot] = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Batched Vertex Buffer (instance data, grown)"),
+            size: new_capacity,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.batched_vertex_buffer_capacity[slot] = new_capacity;
+    }
+
+    /// World 内のエンティティを、テクスチャごとにグループ化してインスタンシング描画する。
+    /// 以前は各エンティティごとに `wgpu::Buffer` と `wgpu::BindGroup` を作り直していたが、
+    /// 同じテクスチャを共有するスプライトはインスタンスデータ（x, y, w, h, z, 色変換, UV範囲）だけを
+    /// 1本のバッファに積み、テクスチャ1つにつき bind group 1回・draw call 1回で描画する。
+    ///
+    /// `draw_sprites_batched` と異なり、グループ化は z ソート後の隣接判定ではなく
+    /// `(テクスチャポインタ, blend_mode)` をキーにしたハッシュマップで行うため、同じ
+    /// テクスチャ・同じ合成方法のスプライトは出現順に関係なく必ず1バッチへまとまる
+    /// （その代わり、テクスチャをまたいだ厳密な z 順は保証しない）。
+    ///
+    /// `view`（スワップチェインのビュー）へ直接は描かず、ポストプロセスチェインの "source"
+    /// （`post_process_target_views[0]`、論理解像度）へ描いてから `run_post_process_chain` を
+    /// 呼び出し、`stretch_mode`/`letterbox_viewport` を適用した最終パスで `view` へブリットする。
+    pub fn draw_world(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        world: &crate::ecs::World,
+    ) {
+        // update 中に camera の position/zoom/rotation が変わっている可能性があるので、
+        // 描画の直前に view_proj を uniform_buffer へ書き込み直す。
+        self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&self.camera.view_proj()));
+
+        // (テクスチャのポインタ, blend_mode) のペアをキーに、インスタンスデータをまとめる
+        struct Batch {
+            texture: Rc<TextureHandle>,
+            blend_mode: BlendMode,
+            instances: Vec<[f32; 17]>,
+        }
+        let mut batches: Vec<Batch> = Vec::new();
+        let mut batch_index: HashMap<(usize, BlendMode), usize> = HashMap::new();
+
+        for (transform, texture, color_multiply, color_add, blend_mode, uv_min, uv_max) in world.query_drawables_with_z() {
+            let key = (Rc::as_ptr(&texture) as usize, blend_mode);
+            let idx = *batch_index.entry(key).or_insert_with(|| {
+                batches.push(Batch { texture: Rc::clone(&texture), blend_mode, instances: Vec::new() });
+                batches.len() - 1
+            });
+            batches[idx]
+                .instances
+                .push(pack_instance(&transform, color_multiply, color_add, uv_min, uv_max));
+        }
+        log::debug!(target: "rendering", "draw_world: {} batches", batches.len());
+
+        // インスタンスデータを1本のバッファへまとめて書き込み、各バッチのインスタンス範囲を控える
+        let mut all_instances: Vec<[f32; 17]> = Vec::new();
+        let mut ranges: Vec<(std::ops::Range<u32>, wgpu::BindGroup, BlendMode)> = Vec::new();
+        for batch in &batches {
+            let start = all_instances.len() as u32;
+            all_instances.extend_from_slice(&batch.instances);
+            let end = all_instances.len() as u32;
+
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&batch.texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&batch.texture.sampler),
+                    },
+                ],
+                label: Some("Batched Texture BindGroup"),
+            });
+            ranges.push((start..end, bind_group, batch.blend_mode));
+        }
+
+        let required_bytes = (all_instances.len() * std::mem::size_of::<[f32; 17]>()) as u64;
+        let slot = self.current_buffer;
+        self.ensure_instance_buffer_capacity(slot, required_bytes.max(1));
+        self.queue.write_buffer(&self.batched_vertex_buffers[slot], 0, bytemuck::cast_slice(&all_instances));
+
+        // Text エンティティは別バッチ（テクスチャではなくグリフアトラス）として積む。
+        // pass を開く前に（ensure_glyph が &mut self を要求するため）頂点/インデックスを確定させる。
+        let mut text_vertices: Vec<[f32; 8]> = Vec::new();
+        let mut text_indices: Vec<u16> = Vec::new();
+        for (transform, text) in world.query_texts() {
+            self.append_text_mesh(
+                &mut text_vertices,
+                &mut text_indices,
+                &text.content,
+                transform.x,
+                transform.y,
+                text.size,
+                text.color,
+            );
+        }
+        // `queue_text` で積まれた使い捨てのテキストも同じバッチへ合流させ、描画し終えたら空にする。
+        let pending_texts = std::mem::take(&mut self.pending_texts);
+        for pending in &pending_texts {
+            self.append_text_mesh(
+                &mut text_vertices,
+                &mut text_indices,
+                &pending.content,
+                pending.x,
+                pending.y,
+                pending.size,
+                pending.color,
+            );
+        }
+        if !text_indices.is_empty() {
+            self.ensure_text_vertex_buffer_capacity((text_vertices.len() * std::mem::size_of::<[f32; 8]>()) as u64);
+            self.ensure_text_index_buffer_capacity((text_indices.len() * std::mem::size_of::<u16>()) as u64);
+            self.queue.write_buffer(&self.text_vertex_buffer, 0, bytemuck::cast_slice(&text_vertices));
+            self.queue.write_buffer(&self.text_index_buffer, 0, bytemuck::cast_slice(&text_indices));
+        }
+        let text_atlas_bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Glyph Atlas BindGroup"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.glyph_atlas.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.glyph_atlas.sampler),
+                },
+            ],
+        });
+
+        {
+            // sprites/text はスワップチェインの `view` へ直接ではなく、ポストプロセスチェインの
+            // "source"（`post_process_target_views[0]`）へ描く。`run_post_process_chain` が
+            // この下で `view` へブリットするところまで面倒を見る。
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("World Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.msaa_framebuffer_view,
+                    resolve_target: Some(&self.post_process_target_views[0]),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: false,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.msaa_depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            pass.set_vertex_buffer(0, self.unit_quad_vertex_buffer.slice(..));
+            pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+            for (instance_range, bind_group, blend_mode) in &ranges {
+                pass.set_pipeline(&self.instanced_pipelines[blend_mode]);
+                pass.set_bind_group(1, bind_group, &[]);
+                pass.set_vertex_buffer(1, self.batched_vertex_buffers[slot].slice(..));
+                pass.draw_indexed(0..6, 0, instance_range.clone());
+            }
+
+            if !text_indices.is_empty() {
+                pass.set_pipeline(&self.text_pipeline_msaa);
+                pass.set_bind_group(1, &text_atlas_bind_group, &[]);
+                pass.set_vertex_buffer(0, self.text_vertex_buffer.slice(..));
+                pass.set_index_buffer(self.text_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                pass.draw_indexed(0..text_indices.len() as u32, 0, 0..1);
+            }
+        }
+
+        self.run_post_process_chain(encoder, view);
+
+        self.current_buffer = (self.current_buffer + 1) % self.batched_vertex_buffers.len();
+    }
+
+    /// draw_sprites_batched は、World 内のエンティティ（Transform と Rc<TextureHandle> のペア）
+    /// を z 値でソートしつつテクスチャごとにグループ化し、インスタンシング描画で一括描画します。
+    /// 以前は drawable 1つにつき頂点4つ・インデックス6つを積んでいたため、`vertex_count_total`
+    /// (u16) が約16K スプライトでオーバーフローする恐れがあった。ユニットクアッドを使い回し、
+    /// インスタンスごとのデータ（x, y, w, h, z, 色変換, UV範囲）だけをバッファに積むことでこれを解消する。
+    ///
+    /// z でソートした後は隣接する要素だけをグループ化するため、同じテクスチャの drawable でも
+    /// 間に別テクスチャが挟まれば別バッチになる。これは意図的なトレードオフで、z 順の正しさを
+    /// バッチ数より優先している。
+    ///
+    /// `draw_world` と同様、`view` へ直接は描かずポストプロセスチェインの "source" へ描いてから
+    /// `run_post_process_chain` で letterbox/stretch を適用して `view` へブリットする。
+    pub fn draw_sprites_batched(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        world: &crate::ecs::World,
+    ) {
+        // update 中に camera の position/zoom/rotation が変わっている可能性があるので、
+        // 描画の直前に view_proj を uniform_buffer へ書き込み直す。
+        self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&self.camera.view_proj()));
+
+        // (1) Query and sort drawables（z でのソートは query_drawables_with_z 内で完了済み）
+        let drawables = world.query_drawables_with_z();
+
+        // (2) Batch creation: グループ化はテクスチャまたは blend_mode の切り替わりでのみ発生する
+        struct Batch {
+            texture_ptr: usize,
+            blend_mode: BlendMode,
+            texture: Rc<TextureHandle>,
+            instances: Vec<[f32; 17]>,
+        }
+        let mut batches: Vec<Batch> = Vec::new();
+        for (transform, texture, color_multiply, color_add, blend_mode, uv_min, uv_max) in drawables {
+            let key = Rc::as_ptr(&texture) as usize;
+            let instance = pack_instance(&transform, color_multiply, color_add, uv_min, uv_max);
+            if let Some(last) = batches.last_mut() {
+                if last.texture_ptr == key && last.blend_mode == blend_mode {
+                    last.instances.push(instance);
+                    continue;
+                }
+            }
+            batches.push(Batch { texture_ptr: key, blend_mode, texture, instances: vec![instance] });
+        }
+        log::debug!(target: "rendering", "Created {} batches", batches.len());
+
+        // (3) Aggregation: インスタンスデータを1本のバッファへ積み、バッチごとのインスタンス範囲を控える
+        struct BatchDrawCall {
+            texture_bg: wgpu::BindGroup,
+            instance_range: std::ops::Range<u32>,
+            blend_mode: BlendMode,
+        }
+        let mut all_instances: Vec<[f32; 17]> = Vec::new();
+        let mut draw_calls = Vec::new();
+
+        for batch in &batches {
+            let texture_bg = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&batch.texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&batch.texture.sampler),
+                    },
+                ],
+                label: Some("Batched Texture BindGroup"),
+            });
+            let start = all_instances.len() as u32;
+            all_instances.extend_from_slice(&batch.instances);
+            let end = all_instances.len() as u32;
+            draw_calls.push(BatchDrawCall { texture_bg, instance_range: start..end, blend_mode: batch.blend_mode });
+        }
+
+        // (4) Buffer write: インスタンスバッファは必要に応じて拡張する
+        let required_bytes = (all_instances.len() * std::mem::size_of::<[f32; 17]>()) as u64;
+        let slot = self.current_buffer;
+        self.ensure_instance_buffer_capacity(slot, required_bytes.max(1));
+        self.queue.write_buffer(&self.batched_vertex_buffers[slot], 0, bytemuck::cast_slice(&all_instances));
+
+        // (5) Render pass and draw calls。`view`（スワップチェイン）ではなくポストプロセス
+        // チェインの "source" へ描き、この下の run_post_process_chain が letterbox/stretch を
+        // 適用して `view` へブリットする。
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Batched Sprite Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.msaa_framebuffer_view,
+                    resolve_target: Some(&self.post_process_target_views[0]),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: false,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.msaa_depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+            pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            pass.set_vertex_buffer(0, self.unit_quad_vertex_buffer.slice(..));
+            pass.set_vertex_buffer(1, self.batched_vertex_buffers[slot].slice(..));
+            pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            for dc in &draw_calls {
+                pass.set_pipeline(&self.instanced_pipelines[&dc.blend_mode]);
+                pass.set_bind_group(1, &dc.texture_bg, &[]);
+                pass.draw_indexed(0..6, 0, dc.instance_range.clone());
+            }
+        }
+
+        self.run_post_process_chain(encoder, view);
+
+        self.current_buffer = (self.current_buffer + 1) % self.batched_vertex_buffers.len();
+    }
+
+    /// draw_sprites_batched と同じ要領でテクスチャごとにバッチを組むが、バッチ構成
+    /// （テクスチャの並びと各バッチのインスタンス数）が前回呼び出し時から変わっていなければ、
+    /// bind group の再作成や draw call の再記録を省いて `wgpu::RenderBundle` を使い回す。
+    /// `layer_id` は呼び出し側が静的なレイヤー（背景や UI など、毎フレーム変化しないもの）
+    /// ごとに割り当てる識別子で、バンドルは `sprite_bundle_cache` にレイヤーごとに保持される
+    /// （1 つのレイヤーを作り直しても他のレイヤーのバンドルはそのまま）。レイヤーの中身を
+    /// 書き換えたら `invalidate_sprite_bundle_cache(layer_id)` を呼んでそのレイヤーのキャッシュ
+    /// だけを破棄し、次の呼び出しで作り直させること。
+    ///
+    /// このメソッドはバンドルを構築・更新するだけで描画は行わない。同じフレームで描画する
+    /// 全レイヤーを `draw_sprites_batched_cached` で更新し終えたら、`present_cached_sprite_layers`
+    /// を 1 回呼んで共有の render pass 上にまとめて `execute_bundles` すること。
+    pub fn draw_sprites_batched_cached(&mut self, world: &crate::ecs::World, layer_id: u64) {
+        // update 中に camera の position/zoom/rotation が変わっている可能性があるので、
+        // 描画の直前に view_proj を uniform_buffer へ書き込み直す。
+        self.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&self.camera.view_proj()));
+
+        let drawables = world.query_drawables_with_z();
+
+        struct Batch {
+            texture_ptr: usize,
+            blend_mode: BlendMode,
+            texture: Rc<TextureHandle>,
+            instances: Vec<[f32; 17]>,
+        }
+        let mut batches: Vec<Batch> = Vec::new();
+        for (transform, texture, color_multiply, color_add, blend_mode, uv_min, uv_max) in drawables {
+            let key = Rc::as_ptr(&texture) as usize;
+            let instance = pack_instance(&transform, color_multiply, color_add, uv_min, uv_max);
+            if let Some(last) = batches.last_mut() {
+                if last.texture_ptr == key && last.blend_mode == blend_mode {
+                    last.instances.push(instance);
+                    continue;
+                }
+            }
+            batches.push(Batch { texture_ptr: key, blend_mode, texture, instances: vec![instance] });
+        }
+
+        let signature: Vec<(usize, BlendMode, u32)> =
+            batches.iter().map(|b| (b.texture_ptr, b.blend_mode, b.instances.len() as u32)).collect();
+
+        let needs_rebuild = match self.sprite_bundle_cache.get(&layer_id) {
+            Some(cached) => cached.signature != signature,
+            None => true,
+        };
+
+        if needs_rebuild {
+            log::debug!(target: "rendering", "draw_sprites_batched_cached: rebuilding bundle for layer {}", layer_id);
+
+            let mut all_instances: Vec<[f32; 17]> = Vec::new();
+            let mut draw_calls: Vec<(wgpu::BindGroup, std::ops::Range<u32>, BlendMode)> = Vec::new();
+            for batch in &batches {
+                let texture_bg = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    layout: &self.texture_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&batch.texture.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&batch.texture.sampler),
+                        },
+                    ],
+                    label: Some("Cached Batched Texture BindGroup"),
+                });
+                let start = all_instances.len() as u32;
+                all_instances.extend_from_slice(&batch.instances);
+                let end = all_instances.len() as u32;
+                draw_calls.push((texture_bg, start..end, batch.blend_mode));
+            }
+
+            // レイヤー専用のインスタンスバッファを作る。self.batched_vertex_buffers のような
+            // 共有バッファにすると、別レイヤーの再構築で書き込んだデータがこのレイヤーの
+            // バンドルからも見えてしまう（同じバッファハンドルを参照するため）。サイズ0の
+            // バッファは作れないので、描画対象が無いレイヤーでも最低1インスタンス分は確保する。
+            let required_bytes = (all_instances.len() * std::mem::size_of::<[f32; 17]>()) as u64;
+            let instance_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Sprite Layer Instance Buffer"),
+                size: required_bytes.max(std::mem::size_of::<[f32; 17]>() as u64),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            if !all_instances.is_empty() {
+                self.queue.write_buffer(&instance_buffer, 0, bytemuck::cast_slice(&all_instances));
+            }
+
+            let mut bundle_encoder =
+                self.device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+                    label: Some("Sprite Layer RenderBundle Encoder"),
+                    color_formats: &[self.surface_format],
+                    depth_stencil: Some(wgpu::RenderBundleDepthStencil {
+                        format: DEPTH_FORMAT,
+                        depth_read_only: false,
+                        stencil_read_only: true,
+                    }),
+                    sample_count: SAMPLE_COUNT,
+                    multiview: None,
+                });
+            bundle_encoder.set_bind_group(0, &self.uniform_bind_group, &[]);
+            bundle_encoder.set_vertex_buffer(0, self.unit_quad_vertex_buffer.slice(..));
+            bundle_encoder.set_vertex_buffer(1, instance_buffer.slice(..));
+            bundle_encoder.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            for (bind_group, instance_range, blend_mode) in &draw_calls {
+                bundle_encoder.set_pipeline(&self.instanced_pipelines[blend_mode]);
+                bundle_encoder.set_bind_group(1, bind_group, &[]);
+                bundle_encoder.draw_indexed(0..6, 0, instance_range.clone());
+            }
+            let bundle = bundle_encoder.finish(&wgpu::RenderBundleDescriptor {
+                label: Some("Sprite Layer RenderBundle"),
+            });
+
+            self.sprite_bundle_cache.insert(layer_id, CachedSpriteLayer { signature, instance_buffer, bundle });
+        } else {
+            log::debug!(target: "rendering", "draw_sprites_batched_cached: reusing bundle for layer {}", layer_id);
+        }
+    }
+
+    /// `draw_sprites_batched_cached` で更新した複数レイヤーのバンドルを、1 つの共有 render
+    /// pass 上にまとめて描画する。`layer_ids` の順に `execute_bundles` するので、背景を先に
+    /// 渡して UI を後に渡せばその順で重なる。render pass 自体（および `Clear`）は 1 回しか
+    /// 開かないため、ここに渡した全レイヤーは互いを消さずに同じターゲットへ積み重なる。
+    /// `layer_ids` に未構築（一度も `draw_sprites_batched_cached` を呼んでいない）の id が
+    /// 含まれていても無視される。
+    ///
+    /// `draw_world`/`draw_sprites_batched` と同様、`view` へ直接ではなくポストプロセスチェインの
+    /// "source" へ描いてから `run_post_process_chain` で letterbox/stretch を適用して `view` へ
+    /// ブリットする。
+    pub fn present_cached_sprite_layers(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        layer_ids: &[u64],
+    ) {
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Cached Batched Sprite Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.msaa_framebuffer_view,
+                    resolve_target: Some(&self.post_process_target_views[0]),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: false,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.msaa_depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: false,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+            for layer_id in layer_ids {
+                if let Some(cached) = self.sprite_bundle_cache.get(layer_id) {
+                    pass.execute_bundles(std::iter::once(&cached.bundle));
+                }
+            }
+        }
+
+        self.run_post_process_chain(encoder, view);
+    }
+
+    /// `draw_sprites_batched_cached` が構築したバンドルキャッシュのうち、指定した `layer_id`
+    /// のものだけを破棄する。そのレイヤーの内容（エンティティの追加・削除・テクスチャ変更
+    /// など）を変更した呼び出し側は、変更を反映させたい次のフレームより前にこれを呼ぶこと。
+    pub fn invalidate_sprite_bundle_cache(&mut self, layer_id: u64) {
+        self.sprite_bundle_cache.remove(&layer_id);
+    }
+
+    /// ポストプロセスのプリセットファイルを読み込み、現在のパスチェインを差し替える。
+    /// 各パスのフラグメントシェーダーを読み込んでパイプラインと uniform バッファを作る
+    /// （頂点シェーダーは全パス共通のフルスクリーン三角形 `post_process_vertex_shader` を使う）。
+    /// 最後のパス以外は `scale` に応じたサイズの自前テクスチャを確保する（等倍ならロジカル
+    /// 解像度そのまま、0.5 ならダウンサンプルしたバッファになる）。
+    pub fn load_post_process_chain(&mut self, preset_path: &str) {
+        let pass_descs = parse_post_process_preset(preset_path);
+        let num_passes = pass_descs.len();
+        let logical_size = self.post_process_targets[0].size();
+        self.post_process_passes = pass_descs
+            .into_iter()
+            .enumerate()
+            .map(|(i, desc)| {
+                let fragment_src = std::fs::read_to_string(&desc.shader_path)
+                    .unwrap_or_else(|e| panic!("Failed to read post-process shader {}: {}", desc.shader_path, e));
+                let fragment_shader = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(&desc.shader_path),
+                    source: wgpu::ShaderSource::Wgsl(fragment_src.into()),
+                });
+                let pipeline = create_post_process_pipeline(
+                    &self.device,
+                    &self.post_process_pipeline_layout,
+                    &self.post_process_vertex_shader,
+                    &fragment_shader,
+                    self.surface_format,
+                );
+                let uniform_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Post Process Uniform Buffer"),
+                    size: (std::mem::size_of::<[f32; 8]>()) as u64,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                let is_last = i == num_passes - 1;
+                let output = if is_last {
+                    None
+                } else {
+                    let width = ((logical_size.width as f32) * desc.scale).max(1.0) as u32;
+                    let height = ((logical_size.height as f32) * desc.scale).max(1.0) as u32;
+                    Some(create_post_process_target(&self.device, self.surface_format, width, height))
+                };
+                PostProcessPass { shader_path: desc.shader_path, pipeline, uniform_buffer, scale: desc.scale, output }
+            })
+            .collect();
+        log::debug!(target: "rendering", "Loaded post-process chain with {} pass(es) from {}", self.post_process_passes.len(), preset_path);
+    }
+
+    /// ポストプロセスチェインを実行する。`post_process_target_views[0]`（sprites の描画先）を
+    /// 起点に、各パスが前段の出力（`PostProcessPass::output`、`scale` に応じたサイズ）を
+    /// サンプルして自分の出力先へ書くフルスクリーンのフラグメントシェーダーを実行し、
+    /// 最終パスだけ `surface_view` へ直接書き込む。最終パスでは `self.letterbox_viewport` で
+    /// ビューポートを絞り、stretch_mode が false のときはサーフェス中央にアスペクト比を
+    /// 保って描画する（はみ出る部分は黒帯のまま残る）。
+    fn run_post_process_chain(&mut self, encoder: &mut wgpu::CommandEncoder, surface_view: &wgpu::TextureView) {
+        self.frame_counter = self.frame_counter.wrapping_add(1);
+        let time = self.start_time.elapsed().as_secs_f32();
+
+        let output_size = (self.config.width, self.config.height);
+        let mut read_view = &self.post_process_target_views[0];
+        let mut read_size = self.post_process_targets[0].size();
+
+        let num_passes = self.post_process_passes.len();
+        for i in 0..num_passes {
+            let is_last = i == num_passes - 1;
+
+            let uniform_data = pack_post_process_uniform(
+                [output_size.0 as f32, output_size.1 as f32],
+                [read_size.width as f32, read_size.height as f32],
+                self.frame_counter as f32,
+                time,
+            );
+            self.queue.write_buffer(&self.post_process_passes[i].uniform_buffer, 0, bytemuck::cast_slice(&uniform_data));
+
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post Process BindGroup"),
+                layout: &self.post_process_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(read_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.post_process_sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.post_process_passes[i].uniform_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let write_view = if is_last {
+                surface_view
+            } else {
+                &self.post_process_passes[i].output.as_ref().expect("non-last pass must own an output texture").1
+            };
+
+            {
+                let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Post Process Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: write_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                pass.set_pipeline(&self.post_process_passes[i].pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                if is_last {
+                    // stretch_mode が false の場合、描画先をサーフェス中央のアスペクト比維持
+                    // 矩形に絞る。ops.load が Color::BLACK でサーフェス全体をクリアしてあるので、
+                    // 矩形の外側はそのまま黒帯として残る。
+                    let vp = &self.letterbox_viewport;
+                    pass.set_viewport(vp.x, vp.y, vp.width, vp.height, 0.0, 1.0);
+                }
+                pass.draw(0..3, 0..1);
+            }
+
+            if !is_last {
+                let (output_texture, output_view) = self.post_process_passes[i].output.as_ref().unwrap();
+                read_size = output_texture.size();
+                read_view = output_view;
+            }
+        }
+    }
+}